@@ -0,0 +1,263 @@
+use crate::data::covid::models::{
+    CountryFigures, CovidCountry, CovidMetric, DATE, ISO_CODE, LOCATION, NEW_CASES, NEW_DEATHS,
+    POPULATION, TOTAL_CASES, TOTAL_DEATHS,
+};
+use crate::data::models::{Aggregation, TimeSeries, TimeSeriesPoint};
+use crate::errors::AppError;
+use chrono::NaiveDate;
+use log::warn;
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::result::Result;
+
+/// The window of the rolling averages of the daily figures, smoothing the weekly reporting patterns.
+pub const ROLLING_DAYS: i64 = 7;
+/// The rates are given per 100'000 inhabitants.
+pub const PER_CAPITA: f32 = 100_000.0;
+
+/// Reads the daily figures per country of an OWID-style CSV, i.e. with a row per country and date.
+/// The reported totals are read if the CSV contains their columns.
+pub fn read_owid_csv(file_path: &Path) -> Result<Vec<CountryFigures>, AppError> {
+    let header = csv_header(file_path)?;
+    let mut columns = vec![ISO_CODE, LOCATION, DATE, NEW_CASES, NEW_DEATHS, POPULATION];
+    columns.extend(
+        [TOTAL_CASES, TOTAL_DEATHS]
+            .into_iter()
+            .filter(|c| header.iter().any(|h| h == c)),
+    );
+    let df = CsvReader::from_path(file_path)?
+        .has_header(true)
+        .with_columns(Some(columns.iter().map(|c| c.to_string()).collect()))
+        // the figures of most countries start with empty values, i.e. the full file is scanned for the types
+        .infer_schema(None)
+        .finish()?;
+    convert_data_frame(&df)
+}
+
+/// The column names of the CSV, i.e. its first line.
+fn csv_header(file_path: &Path) -> Result<Vec<String>, AppError> {
+    let mut header = String::new();
+    BufReader::new(File::open(file_path)?).read_line(&mut header)?;
+    let columns = header
+        .trim_end()
+        .split(',')
+        .map(|c| c.trim_matches('"').to_string())
+        .collect();
+    Ok(columns)
+}
+
+fn utf8_values(df: &DataFrame, col_name: &str) -> Result<Vec<Option<String>>, AppError> {
+    let series = df.column(col_name)?.cast(&DataType::Utf8)?;
+    let values = series
+        .utf8()?
+        .into_iter()
+        .map(|v| v.map(|v| v.to_string()))
+        .collect();
+    Ok(values)
+}
+
+fn f32_values(df: &DataFrame, col_name: &str) -> Result<Vec<Option<f32>>, AppError> {
+    let series = df.column(col_name)?.cast(&DataType::Float32)?;
+    let values = series.f32()?.into_iter().collect();
+    Ok(values)
+}
+
+/// The values of an optional column, all missing if the data frame does not contain it.
+fn optional_f32_values(df: &DataFrame, col_name: &str) -> Result<Vec<Option<f32>>, AppError> {
+    if df.get_column_names().contains(&col_name) {
+        f32_values(df, col_name)
+    } else {
+        Ok(vec![None; df.height()])
+    }
+}
+
+/// The points of the figures of a country, collected row by row.
+#[derive(Default)]
+struct CountryPoints {
+    new_cases: Vec<TimeSeriesPoint>,
+    new_deaths: Vec<TimeSeriesPoint>,
+    total_cases: Vec<TimeSeriesPoint>,
+    total_deaths: Vec<TimeSeriesPoint>,
+}
+
+/// Converts the rows into the daily figures per country, ordered by the iso code.
+/// Rows without iso code or with an invalid date are skipped, missing figures are omitted.
+pub fn convert_data_frame(df: &DataFrame) -> Result<Vec<CountryFigures>, AppError> {
+    let iso_codes = utf8_values(df, ISO_CODE)?;
+    let names = utf8_values(df, LOCATION)?;
+    let dates = utf8_values(df, DATE)?;
+    let new_cases = f32_values(df, NEW_CASES)?;
+    let new_deaths = f32_values(df, NEW_DEATHS)?;
+    let populations = f32_values(df, POPULATION)?;
+    let total_cases = optional_f32_values(df, TOTAL_CASES)?;
+    let total_deaths = optional_f32_values(df, TOTAL_DEATHS)?;
+
+    // the country along with the points of its figures per iso code
+    let mut points_by_country: BTreeMap<String, (CovidCountry, CountryPoints)> = BTreeMap::new();
+    for idx in 0..df.height() {
+        let (iso_code, date) = match (&iso_codes[idx], &dates[idx]) {
+            (Some(iso_code), Some(date)) => (iso_code, date),
+            _ => continue,
+        };
+        let date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => {
+                warn!(
+                    "Skipping the row of {} with the invalid date '{}'",
+                    iso_code, date
+                );
+                continue;
+            }
+        };
+        let (country, points) = points_by_country
+            .entry(iso_code.clone())
+            .or_insert_with(|| {
+                let country = CovidCountry {
+                    iso_code: iso_code.clone(),
+                    name: names[idx].clone().unwrap_or_else(|| iso_code.clone()),
+                    population: None,
+                };
+                (country, CountryPoints::default())
+            });
+        if populations[idx].is_some() {
+            country.population = populations[idx];
+        }
+        for (values, points) in [
+            (&new_cases, &mut points.new_cases),
+            (&new_deaths, &mut points.new_deaths),
+            (&total_cases, &mut points.total_cases),
+            (&total_deaths, &mut points.total_deaths),
+        ] {
+            if let Some(value) = values[idx] {
+                points.push(TimeSeriesPoint { date, value });
+            }
+        }
+    }
+
+    let figures = points_by_country
+        .into_values()
+        .map(|(country, points)| CountryFigures {
+            country,
+            new_cases: TimeSeries::new(points.new_cases),
+            new_deaths: TimeSeries::new(points.new_deaths),
+            total_cases: TimeSeries::new(points.total_cases),
+            total_deaths: TimeSeries::new(points.total_deaths),
+        })
+        .collect();
+    Ok(figures)
+}
+
+/// Selects the countries by their iso codes, in the given order.
+pub fn filter_countries(figures: &[CountryFigures], iso_codes: &[String]) -> Vec<CountryFigures> {
+    iso_codes
+        .iter()
+        .flat_map(|iso_code| {
+            let country_figures = figures
+                .iter()
+                .find(|f| f.country.iso_code.eq_ignore_ascii_case(iso_code));
+            if country_figures.is_none() {
+                warn!("No figures found for the country {}", iso_code);
+            }
+            country_figures.cloned()
+        })
+        .collect()
+}
+
+/// The running total of the daily figures, including the corrections (negative values) of the reports.
+pub fn cumulative(ts: &TimeSeries) -> TimeSeries {
+    let mut total = 0.0;
+    ts.iter()
+        .map(|tsp| {
+            total += tsp.value;
+            TimeSeriesPoint {
+                date: tsp.date,
+                value: total,
+            }
+        })
+        .collect()
+}
+
+/// The reported totals, resp. the running total of the daily figures if no totals are reported.
+/// The latter undercounts if daily figures are missing.
+fn reported_or_cumulative(totals: &TimeSeries, daily: &TimeSeries) -> TimeSeries {
+    if totals.is_empty() {
+        cumulative(daily)
+    } else {
+        totals.clone()
+    }
+}
+
+pub fn metric_series(figures: &CountryFigures, metric: CovidMetric) -> TimeSeries {
+    match metric {
+        CovidMetric::NewCases => figures.new_cases.clone(),
+        CovidMetric::NewDeaths => figures.new_deaths.clone(),
+        CovidMetric::TotalCases => reported_or_cumulative(&figures.total_cases, &figures.new_cases),
+        CovidMetric::TotalDeaths => {
+            reported_or_cumulative(&figures.total_deaths, &figures.new_deaths)
+        }
+    }
+}
+
+/// The average of the trailing 7 days, see `ROLLING_DAYS`.
+pub fn seven_day_average(ts: &TimeSeries) -> TimeSeries {
+    ts.rolling(ROLLING_DAYS, Aggregation::Mean)
+}
+
+/// The rate per 100'000 inhabitants (see `PER_CAPITA`), none without (positive) population.
+pub fn per_capita(ts: &TimeSeries, population: Option<f32>) -> Option<TimeSeries> {
+    let population = population.filter(|p| *p > 0.0)?;
+    Some(ts.map(|v| v / population * PER_CAPITA))
+}
+
+/// Prints the totals per country, i.e. the latest reported totals or the sums of the daily figures otherwise.
+pub fn print_country_summary(figures: &[CountryFigures]) {
+    println!(
+        "{:>30} | {:>13} | {:>12} | {:>10} | {:>14} | {:>15} | {:>10}",
+        "country",
+        "population",
+        "total cases",
+        "deaths",
+        "cases per 100k",
+        "deaths per 100k",
+        "peak date"
+    );
+    for country_figures in figures.iter() {
+        let country = &country_figures.country;
+        let total = |totals: &TimeSeries, daily: &TimeSeries| match totals.last() {
+            Some(tsp) => tsp.value,
+            None => daily.iter().map(|tsp| tsp.value).sum(),
+        };
+        let total_cases = total(&country_figures.total_cases, &country_figures.new_cases);
+        let total_deaths = total(&country_figures.total_deaths, &country_figures.new_deaths);
+        let rate = |total: f32| {
+            country
+                .population
+                .filter(|p| *p > 0.0)
+                .map(|p| format!("{:.1}", total / p * PER_CAPITA))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        // the peak of the smoothed cases, as the daily reports fluctuate strongly
+        let peak_date = seven_day_average(&country_figures.new_cases)
+            .iter()
+            .filter(|tsp| !tsp.value.is_nan())
+            .max_by(|a, b| a.value.total_cmp(&b.value))
+            .map(|tsp| tsp.date.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:>30} | {:>13} | {:>12.0} | {:>10.0} | {:>14} | {:>15} | {:>10}",
+            country.to_string(),
+            country
+                .population
+                .map(|p| format!("{:.0}", p))
+                .unwrap_or_else(|| "-".to_string()),
+            total_cases,
+            total_deaths,
+            rate(total_cases),
+            rate(total_deaths),
+            peak_date
+        );
+    }
+}
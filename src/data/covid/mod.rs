@@ -0,0 +1,8 @@
+mod conversions;
+pub mod models;
+
+pub use conversions::{
+    filter_countries, metric_series, per_capita, print_country_summary, read_owid_csv,
+    seven_day_average, ROLLING_DAYS,
+};
+pub use models::{CountryFigures, CovidMetric, OWID_FILE_NAME};
@@ -0,0 +1,71 @@
+use crate::data::models::TimeSeries;
+use std::fmt;
+
+/// The file name of the OWID dataset within the data directory,
+/// e.g. downloaded from https://covid.ourworldindata.org/data/owid-covid-data.csv
+pub const OWID_FILE_NAME: &str = "owid-covid-data.csv";
+
+/// The columns of the OWID dataset which are loaded, further columns are ignored.
+pub const ISO_CODE: &str = "iso_code";
+pub const LOCATION: &str = "location";
+pub const DATE: &str = "date";
+pub const NEW_CASES: &str = "new_cases";
+pub const NEW_DEATHS: &str = "new_deaths";
+pub const POPULATION: &str = "population";
+/// The optional columns of the reported running totals, which include the figures missing as daily values.
+pub const TOTAL_CASES: &str = "total_cases";
+pub const TOTAL_DEATHS: &str = "total_deaths";
+
+/// A country of the dataset, or an aggregate such as the 'World' (iso codes starting with 'OWID_').
+#[derive(Debug, Clone, PartialEq)]
+pub struct CovidCountry {
+    pub iso_code: String,
+    pub name: String,
+    /// The latest population reported, none if not available
+    pub population: Option<f32>,
+}
+
+impl fmt::Display for CovidCountry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.iso_code)
+    }
+}
+
+/// The daily figures of a country.
+#[derive(Debug, Clone)]
+pub struct CountryFigures {
+    pub country: CovidCountry,
+    pub new_cases: TimeSeries,
+    pub new_deaths: TimeSeries,
+    /// The reported running totals, empty if the columns are not contained in the CSV
+    pub total_cases: TimeSeries,
+    pub total_deaths: TimeSeries,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CovidMetric {
+    #[clap(name = "new_cases")]
+    NewCases,
+    #[clap(name = "new_deaths")]
+    NewDeaths,
+    #[clap(name = "total_cases")]
+    TotalCases,
+    #[clap(name = "total_deaths")]
+    TotalDeaths,
+}
+
+impl CovidMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CovidMetric::NewCases => "new_cases",
+            CovidMetric::NewDeaths => "new_deaths",
+            CovidMetric::TotalCases => "total_cases",
+            CovidMetric::TotalDeaths => "total_deaths",
+        }
+    }
+
+    /// Whether the metric is a daily figure rather than a cumulative one, i.e. smoothed by the rolling average.
+    pub fn is_daily(&self) -> bool {
+        matches!(self, CovidMetric::NewCases | CovidMetric::NewDeaths)
+    }
+}
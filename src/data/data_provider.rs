@@ -1,21 +1,208 @@
+use crate::data::covid::{read_owid_csv, CountryFigures, OWID_FILE_NAME};
+use crate::data::dataset::models::{AS_OF_DATE_FORMAT, AS_OF_DATE_PLACEHOLDER};
+use crate::data::dataset::{parse_csv, parse_json, Dataset, DatasetConfig, DatasetFormat};
 use crate::data::football::models::FootballSeasonResults;
-use crate::data::weather::models::WeatherResponse;
+use crate::data::http::HttpClient;
+use crate::data::validation::{
+    validate_football_results, validate_hourly_weather_forecast, validate_weather_forecast,
+};
+use crate::data::weather::models::{as_of_date_format, WeatherResponse};
+use crate::data::weather::{EnsembleApi, ForecastArchive, Resolution};
 use crate::data::FootballLeague;
 use crate::data::WeatherLocation;
+use crate::data::API_SPORTS_PLAYERS_URL;
 use crate::errors::AppError;
-use chrono::Date;
+use chrono::DateTime;
 use chrono::NaiveDate;
-use chrono::Utc;
-use std::fs::create_dir;
+use chrono_tz::Tz;
+use log::{info, warn};
+use std::fs::{create_dir_all, read_dir, remove_file, OpenOptions};
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::result::Result;
 
 type ApiKey = String;
 
-const DATA_PATH: &str = "data";
+/// The default root directory of the data, relative to the working directory.
+pub const DEFAULT_DATA_ROOT: &str = "data";
 const WEATHER: &str = "weather";
+const WEATHER_HOURLY: &str = "weather_hourly";
+const WEATHER_ENSEMBLE: &str = "weather_ensemble";
+/// The as-of hour of the hourly forecasts within their file names, e.g. '2022-07-25T06.json'.
+const HOURLY_AS_OF_FORMAT: &str = "%Y-%m-%dT%H";
 const FOOTBALL: &str = "football";
+const COVID: &str = "covid";
+const LOCK_FILE: &str = ".lock";
+/// The sub-directory of the responses which failed the validation.
+const INVALID: &str = "invalid";
+
+/// The compression of the stored data files, files of any compression are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    const ALL: [Compression; 3] = [Compression::None, Compression::Gzip, Compression::Zstd];
+
+    /// The extension appended to the '.json' extension of the files.
+    fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+}
+
+/// The outcome of `save_validated` for valid content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SaveOutcome {
+    Saved,
+    /// The content is identical to the file of the given name, i.e. it was not saved again
+    Duplicate(String),
+}
+
+/// Saves the content unless issues were found, in which case the content is saved along with the issues
+/// into the `invalid` sub-directory for debugging and an error is returned.
+/// Content with the same hash as a previously saved file is skipped, e.g. a stale forecast served again by the API;
+/// the skipped file name is recorded in the hash index nevertheless, such that the run counts as done (see `is_recorded`).
+fn save_validated<T: serde::Serialize + std::fmt::Debug>(
+    dir: &Path,
+    file_name: &str,
+    content: T,
+    issues: Vec<String>,
+    content_hash: &str,
+    compression: Compression,
+) -> Result<SaveOutcome, AppError> {
+    if issues.is_empty() {
+        let mut hash_index = utils::HashIndex::load(dir)?;
+        if let Some(existing) = hash_index.file_name(content_hash).cloned() {
+            info!(
+                "Skipping {}, the content is identical to {}",
+                file_name, existing
+            );
+            hash_index.insert(content_hash, file_name)?;
+            return Ok(SaveOutcome::Duplicate(existing));
+        }
+        let file_path = dir.join(file_name);
+        println!("Saving data to {:?}", &file_path);
+        utils::save_file(&file_path, content, compression)?;
+        hash_index.insert(content_hash, file_name)?;
+        return Ok(SaveOutcome::Saved);
+    }
+
+    let invalid_dir = dir.join(INVALID);
+    create_dir_all(&invalid_dir)?;
+    let file_path = invalid_dir.join(file_name);
+    warn!(
+        "Invalid response with {} issue(s), saving it to {:?}",
+        issues.len(),
+        &file_path
+    );
+    let rejected = serde_json::json!({
+        "issues": issues,
+        "content": serde_json::to_value(&content)?,
+    });
+    utils::save_file(&file_path, rejected, compression)?;
+
+    Err(AppError::InvalidPayload(format!(
+        "{} ({})",
+        issues.join("; "),
+        file_path.display()
+    )))
+}
+
+/// Whether the (json) file is stored in any compression or was skipped as a duplicate, see `save_validated`.
+fn is_recorded(dir: &Path, file_name: &str) -> bool {
+    utils::find_file(dir, file_name).is_some()
+        || utils::HashIndex::load(dir)
+            .map(|hash_index| hash_index.contains(file_name))
+            .unwrap_or(false)
+}
+
+/// A lock on the data directory, preventing concurrent runs from fetching and writing the same data.
+/// The lock is released when dropped, the lock of a run which is gone (e.g. crashed) is taken over.
+pub struct DataLock {
+    path: PathBuf,
+}
+
+impl DataLock {
+    pub fn acquire(data_root: &Path) -> Result<Self, AppError> {
+        create_dir_all(data_root)?;
+        let path = data_root.join(LOCK_FILE);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                writeln!(file, "{}", std::process::id())?;
+                Ok(Self { path })
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                match Self::stale_pid(&path) {
+                    Some(pid) => {
+                        warn!(
+                            "Taking over the lock {:?} of the run {} which is gone",
+                            path, pid
+                        );
+                        remove_file(&path)?;
+                        let mut file = OpenOptions::new()
+                            .write(true)
+                            .create_new(true)
+                            .open(&path)
+                            .map_err(|_| AppError::Locked(path.clone()))?;
+                        writeln!(file, "{}", std::process::id())?;
+                        Ok(Self { path })
+                    }
+                    None => Err(AppError::Locked(path)),
+                }
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The process id of the lock if the process is gone, none while it runs or if it cannot be determined.
+    fn stale_pid(path: &Path) -> Option<u32> {
+        let pid = std::fs::read_to_string(path)
+            .ok()?
+            .trim()
+            .parse::<u32>()
+            .ok()?;
+        (!process_exists(pid)).then_some(pid)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_exists(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_exists(pid: u32) -> bool {
+    // signal 0 only checks whether the process exists
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+#[cfg(not(unix))]
+fn process_exists(_pid: u32) -> bool {
+    // cannot be determined, the lock is kept
+    true
+}
+
+impl Drop for DataLock {
+    fn drop(&mut self) {
+        if let Err(e) = remove_file(&self.path) {
+            warn!("cannot release the lock {:?}: {}", self.path, e);
+        }
+    }
+}
 
 pub trait TimeSeriesSortKey {
     fn date(&self) -> NaiveDate;
@@ -23,13 +210,13 @@ pub trait TimeSeriesSortKey {
 
 impl TimeSeriesSortKey for WeatherResponse {
     fn date(&self) -> NaiveDate {
-        self.date().naive_utc()
+        self.as_of_date
     }
 }
 
 impl TimeSeriesSortKey for FootballSeasonResults {
     fn date(&self) -> NaiveDate {
-        NaiveDate::from_ymd(self.season, 1, 1)
+        NaiveDate::from_ymd_opt(self.season, 1, 1).unwrap()
     }
 }
 
@@ -48,102 +235,727 @@ pub trait DataProvider {
 
 pub struct WeatherProvider {
     api_key: ApiKey,
+    data_root: PathBuf,
     location: WeatherLocation,
+    client: HttpClient,
+    /// The url of the API, by default the one of the resolution
+    url: Option<String>,
+    compression: Compression,
+    resolution: Resolution,
 }
 
 impl DataProvider for WeatherProvider {
+    /// The directory of the forecasts of the location, separate for each resolution.
     fn data_path(&self) -> PathBuf {
-        Path::new(DATA_PATH)
-            .join(WEATHER)
-            .join(&self.location.to_string())
+        let resolution_dir = match self.resolution {
+            Resolution::Daily => WEATHER,
+            Resolution::Hourly => WEATHER_HOURLY,
+        };
+        self.data_root
+            .join(resolution_dir)
+            .join(self.location.to_string())
     }
 }
 
 impl WeatherProvider {
-    pub fn new(api_key: ApiKey, location: WeatherLocation) -> Self {
-        Self { api_key, location }
+    pub fn new(api_key: ApiKey, location: WeatherLocation, client: HttpClient) -> Self {
+        Self {
+            api_key,
+            data_root: PathBuf::from(DEFAULT_DATA_ROOT),
+            location,
+            client,
+            url: None,
+            compression: Compression::default(),
+            resolution: Resolution::default(),
+        }
+    }
+
+    /// Requests the forecasts from another url, e.g. a local stub server.
+    pub fn with_url(mut self, url: String) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    pub fn with_resolution(mut self, resolution: Resolution) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_data_root(mut self, data_root: PathBuf) -> Self {
+        self.data_root = data_root;
+        self
     }
 
-    pub fn update_data(&self, as_of: &Date<Utc>) -> Result<(), AppError> {
+    pub fn location(&self) -> &WeatherLocation {
+        &self.location
+    }
+
+    /// The file name of the forecast as of the date, and the one used before the migration to naive dates.
+    fn file_names(as_of_date: &NaiveDate) -> (String, String) {
+        (
+            format!("{}.json", as_of_date.format(as_of_date_format::FORMAT)),
+            format!(
+                "{}.json",
+                as_of_date.format(as_of_date_format::LEGACY_FORMAT)
+            ),
+        )
+    }
+
+    /// The file name of the forecast as of the (local) time, i.e. of its date resp. hour for hourly forecasts.
+    fn file_name(&self, as_of: &DateTime<Tz>) -> String {
+        match self.resolution {
+            Resolution::Daily => Self::file_names(&as_of.naive_local().date()).0,
+            Resolution::Hourly => format!("{}.json", as_of.format(HOURLY_AS_OF_FORMAT)),
+        }
+    }
+
+    /// Whether a forecast as of the date is stored (in any compression) or was skipped as a duplicate,
+    /// i.e. any run of the date for hourly forecasts.
+    pub fn has_data(&self, as_of_date: &NaiveDate) -> bool {
+        if self.resolution == Resolution::Hourly {
+            return self.stored_as_of_dates().any(|date| date == *as_of_date);
+        }
+        let (file_name, legacy_file_name) = Self::file_names(as_of_date);
         let zip_country_path = self.data_path();
+        is_recorded(&zip_country_path, &file_name)
+            || utils::find_file(&zip_country_path, &legacy_file_name).is_some()
+    }
 
-        if !zip_country_path.exists() {
-            create_dir("/some/dir")?;
+    /// Whether the forecast as of the (local) time is stored or was skipped as a duplicate,
+    /// i.e. as of its date resp. hour for hourly forecasts.
+    pub fn has_run(&self, as_of: &DateTime<Tz>) -> bool {
+        match self.resolution {
+            Resolution::Daily => self.has_data(&as_of.naive_local().date()),
+            Resolution::Hourly => is_recorded(&self.data_path(), &self.file_name(as_of)),
         }
+    }
+
+    /// The as-of dates of the stored forecasts, repeated for several runs of hourly forecasts on the same date.
+    fn stored_as_of_dates(&self) -> impl Iterator<Item = NaiveDate> {
+        read_dir(self.data_path())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .flat_map(|entry| {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                // the file names start with the date, followed by the timezone (legacy) or the hour (hourly)
+                let date = utils::json_file_stem(&file_name)?.get(..10)?.to_string();
+                NaiveDate::parse_from_str(&date, as_of_date_format::FORMAT).ok()
+            })
+    }
+
+    /// The most recent as-of date of the stored forecasts.
+    pub fn latest_as_of_date(&self) -> Option<NaiveDate> {
+        self.stored_as_of_dates().max()
+    }
+
+    /// Fetches the forecast as of the (local) date resp. hour of the location, unless already stored.
+    pub fn update_data(&self, as_of: &DateTime<Tz>) -> Result<(), AppError> {
+        let zip_country_path = self.data_path();
+        create_dir_all(&zip_country_path)?;
 
-        let file_name = format!("{}.json", as_of);
-        let file = Path::new(&file_name);
-        let file_path = zip_country_path.join(file);
+        let file_name = self.file_name(as_of);
 
         // update the data
-        if !Path::new(&file_path).exists() {
+        if !self.has_run(as_of) {
             println!("Requesting weather forecast");
-            let weather_forecast = self.location.load_weather_data(&self.api_key)?;
+            let url = self.url.as_deref().unwrap_or_else(|| self.resolution.url());
+            let weather_forecast = self.location.load_weather_data(
+                &self.client,
+                url,
+                &self.api_key,
+                as_of,
+                self.resolution,
+            )?;
 
-            println!("Saving Weather forecast to {:?}", &file_path);
-            utils::save_file(&file_path, weather_forecast)?;
+            let issues = match self.resolution {
+                Resolution::Daily => validate_weather_forecast(&weather_forecast.forecast),
+                Resolution::Hourly => validate_hourly_weather_forecast(&weather_forecast.forecast),
+            };
+            // the forecast itself is hashed, as the as-of date and fetch time differ for each response
+            let content_hash = utils::content_hash(&weather_forecast.forecast)?;
+            match save_validated(
+                &zip_country_path,
+                &file_name,
+                weather_forecast,
+                issues,
+                &content_hash,
+                self.compression,
+            )? {
+                SaveOutcome::Saved => println!("Successfully saved weather forecast"),
+                SaveOutcome::Duplicate(existing) => println!(
+                    "Weather forecast not saved, it is identical to {}",
+                    existing
+                ),
+            }
+        }
 
-            println!("Successfully saved weather forecast");
+        Ok(())
+    }
+
+    /// Backfills the forecasts as of the dates from `from` to `to` (inclusive) from the archive, unless already stored.
+    /// Invalid forecasts are saved for debugging and skipped, see `save_validated`.
+    /// Returns the number of backfilled forecasts, excluding the duplicates of stored forecasts.
+    pub fn backfill_data(
+        &self,
+        archive: &ForecastArchive,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<usize, AppError> {
+        let zip_country_path = self.data_path();
+        create_dir_all(&zip_country_path)?;
+        let coordinates = archive.coordinates(&self.client, &self.location)?;
+        info!(
+            "Backfilling the forecasts for {} at {:?}",
+            self.location, coordinates
+        );
+
+        let mut backfilled = 0;
+        let mut as_of_date = from;
+        while as_of_date <= to {
+            if !self.has_data(&as_of_date) {
+                let weather_forecast = archive.load_weather_data(
+                    &self.client,
+                    &self.location,
+                    &coordinates,
+                    &as_of_date,
+                )?;
+                let (file_name, _) = Self::file_names(&as_of_date);
+                let issues = validate_weather_forecast(&weather_forecast.forecast);
+                let content_hash = utils::content_hash(&weather_forecast.forecast)?;
+                match save_validated(
+                    &zip_country_path,
+                    &file_name,
+                    weather_forecast,
+                    issues,
+                    &content_hash,
+                    self.compression,
+                ) {
+                    Ok(SaveOutcome::Saved) => backfilled += 1,
+                    Ok(SaveOutcome::Duplicate(_)) => {}
+                    Err(AppError::InvalidPayload(e)) => {
+                        warn!("Skipping the forecast as of {}: {}", as_of_date, e)
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            as_of_date = match as_of_date.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        Ok(backfilled)
+    }
+
+    /// The directory of the ensemble forecasts of the location, with a sub-directory of the members per as-of date.
+    fn ensemble_path(&self) -> PathBuf {
+        self.data_root
+            .join(WEATHER_ENSEMBLE)
+            .join(self.location.to_string())
+    }
+
+    fn ensemble_members_path(&self, as_of_date: &NaiveDate) -> PathBuf {
+        self.ensemble_path()
+            .join(as_of_date.format(as_of_date_format::FORMAT).to_string())
+    }
+
+    /// Fetches the members of the ensemble forecast as of the (local) date of the location, unless already stored.
+    /// Invalid members are saved for debugging and skipped, see `save_validated`.
+    pub fn update_ensemble_data(
+        &self,
+        api: &EnsembleApi,
+        as_of: &DateTime<Tz>,
+    ) -> Result<(), AppError> {
+        let members_path = self.ensemble_members_path(&as_of.naive_local().date());
+        if read_dir(&members_path)
+            .ok()
+            .and_then(|mut entries| entries.next())
+            .is_some()
+        {
+            return Ok(());
+        }
+        create_dir_all(&members_path)?;
+
+        println!("Requesting ensemble forecast");
+        let coordinates = api.coordinates(&self.client, &self.location)?;
+        let members = api.load_members(&self.client, &self.location, &coordinates, as_of)?;
+        for member in members {
+            let file_name = format!("member_{:02}.json", member.member.unwrap_or_default());
+            let issues = validate_weather_forecast(&member.forecast);
+            let content_hash = utils::content_hash(&member.forecast)?;
+            match save_validated(
+                &members_path,
+                &file_name,
+                member,
+                issues,
+                &content_hash,
+                self.compression,
+            ) {
+                Ok(_) => {}
+                Err(AppError::InvalidPayload(e)) => {
+                    warn!("Skipping the ensemble member {}: {}", file_name, e)
+                }
+                Err(e) => return Err(e),
+            }
         }
+        println!("Successfully saved ensemble forecast");
 
         Ok(())
     }
+
+    /// The most recent as-of date of the stored ensemble forecasts.
+    pub fn latest_ensemble_as_of_date(&self) -> Option<NaiveDate> {
+        read_dir(self.ensemble_path())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .flat_map(|entry| {
+                let dir_name = entry.file_name().to_string_lossy().to_string();
+                NaiveDate::parse_from_str(&dir_name, as_of_date_format::FORMAT).ok()
+            })
+            .max()
+    }
+
+    /// Loads the members of the ensemble forecast as of the date, ordered by their number.
+    pub fn load_ensemble_data(
+        &self,
+        as_of_date: &NaiveDate,
+    ) -> Result<Vec<WeatherResponse>, AppError> {
+        let mut members: Vec<WeatherResponse> =
+            utils::read_files(&self.ensemble_members_path(as_of_date))?;
+        members.sort_by_key(|m| m.member);
+        Ok(members)
+    }
 }
 
 pub struct FootballProvider {
-    api_key: ApiKey, // TODO: load the data
+    api_key: ApiKey,
+    data_root: PathBuf,
     league: FootballLeague,
+    client: HttpClient,
+    url: String,
+    compression: Compression,
 }
 
 impl FootballProvider {
-    pub fn new(api_key: ApiKey, league: FootballLeague) -> Self {
-        Self { api_key, league }
+    pub fn new(api_key: ApiKey, league: FootballLeague, client: HttpClient) -> Self {
+        Self {
+            api_key,
+            data_root: PathBuf::from(DEFAULT_DATA_ROOT),
+            league,
+            client,
+            url: API_SPORTS_PLAYERS_URL.to_string(),
+            compression: Compression::default(),
+        }
+    }
+
+    /// Requests the statistics from another url, e.g. a local stub server.
+    pub fn with_url(mut self, url: String) -> Self {
+        self.url = url;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_data_root(mut self, data_root: PathBuf) -> Self {
+        self.data_root = data_root;
+        self
+    }
+
+    /// The directory of the league named by its country only, as before the leagues were identified by their id.
+    /// It is used as long as the directory of the league does not exist, none otherwise.
+    pub fn legacy_data_path(&self) -> Option<PathBuf> {
+        let football_path = self.data_root.join(FOOTBALL);
+        let legacy_path = football_path.join(self.league.country.to_lowercase());
+        let uses_legacy_path =
+            !football_path.join(self.league.to_string()).exists() && legacy_path.is_dir();
+        uses_legacy_path.then_some(legacy_path)
+    }
+
+    /// Fetches the player statistics of the season (all pages), unless already stored.
+    pub fn update_data(&self, season: i32) -> Result<(), AppError> {
+        let league_path = self.data_path();
+        create_dir_all(&league_path)?;
+
+        let file_name = format!("{}.json", season);
+        if utils::find_file(&league_path, &file_name).is_some() {
+            return Ok(());
+        }
+
+        let mut player_results: Vec<serde_json::Value> = Vec::new();
+        let mut page = 1;
+        loop {
+            info!(
+                "Requesting page {} of season {} of {}",
+                page, season, self.league.name
+            );
+            let query = [
+                ("league", self.league.id.to_string()),
+                ("season", season.to_string()),
+                ("page", page.to_string()),
+            ];
+            let headers = [("x-apisports-key", self.api_key.clone())];
+            let resp_json = self.client.get_json(&self.url, &query, &headers)?;
+
+            // the API reports errors, e.g. an exhausted quota, with a successful status
+            let has_errors = match &resp_json["errors"] {
+                serde_json::Value::Array(errors) => !errors.is_empty(),
+                serde_json::Value::Object(errors) => !errors.is_empty(),
+                _ => false,
+            };
+            if has_errors {
+                return Err(AppError::Http(format!(
+                    "{} reports errors: {}",
+                    self.url, resp_json["errors"]
+                )));
+            }
+
+            if let Some(results) = resp_json["response"].as_array() {
+                player_results.extend(results.iter().cloned());
+            }
+            let total_pages = resp_json["paging"]["total"].as_i64().unwrap_or(1);
+            if page >= total_pages {
+                break;
+            }
+            page += 1;
+        }
+
+        let season_results = serde_json::json!({
+            "season": season,
+            "playerResults": player_results,
+        });
+        let issues = validate_football_results(&season_results);
+        let content_hash = utils::content_hash(&season_results)?;
+        save_validated(
+            &league_path,
+            &file_name,
+            season_results,
+            issues,
+            &content_hash,
+            self.compression,
+        )?;
+        Ok(())
     }
 }
 
 impl DataProvider for FootballProvider {
     fn data_path(&self) -> PathBuf {
-        Path::new(DATA_PATH)
-            .join(FOOTBALL)
-            .join(&self.league.to_string())
+        self.legacy_data_path()
+            .unwrap_or_else(|| self.data_root.join(FOOTBALL).join(self.league.to_string()))
+    }
+}
+
+/// Provides the COVID-19 figures of an OWID-style CSV, imported into the data directory as it is not fetched by an API.
+pub struct CovidProvider {
+    data_root: PathBuf,
+}
+
+impl DataProvider for CovidProvider {
+    fn data_path(&self) -> PathBuf {
+        self.data_root.join(COVID)
+    }
+}
+
+impl Default for CovidProvider {
+    fn default() -> Self {
+        Self {
+            data_root: PathBuf::from(DEFAULT_DATA_ROOT),
+        }
+    }
+}
+
+impl CovidProvider {
+    pub fn with_data_root(mut self, data_root: PathBuf) -> Self {
+        self.data_root = data_root;
+        self
+    }
+
+    fn file_path(&self) -> PathBuf {
+        self.data_path().join(OWID_FILE_NAME)
+    }
+
+    /// Imports the CSV file into the data directory, replacing a previous import.
+    /// The file is copied to a temporary file first and renamed, such that an interrupted import keeps the previous one.
+    pub fn import(&self, file_path: &Path) -> Result<(), AppError> {
+        let data_path = self.data_path();
+        create_dir_all(&data_path)?;
+        let target = self.file_path();
+        let tmp_path = data_path.join(format!(".{}.tmp", OWID_FILE_NAME));
+        std::fs::copy(file_path, &tmp_path)?;
+        std::fs::rename(&tmp_path, &target)?;
+        println!("Imported {:?} to {:?}", file_path, target);
+        Ok(())
+    }
+
+    /// Loads the daily figures of all countries of the imported CSV.
+    pub fn load_data(&self) -> Result<Vec<CountryFigures>, AppError> {
+        let file_path = self.file_path();
+        if !file_path.is_file() {
+            return Err(AppError::SetupDataset(format!(
+                "{:?} not found, import an OWID-style CSV first",
+                file_path
+            )));
+        }
+        read_owid_csv(&file_path)
+    }
+}
+
+/// Loads a tabular dataset from files resp. an url as configured, see `DatasetConfig`.
+pub struct DatasetProvider {
+    config: DatasetConfig,
+    data_root: PathBuf,
+    client: HttpClient,
+}
+
+impl DatasetProvider {
+    pub fn new(config: DatasetConfig, client: HttpClient) -> Self {
+        Self {
+            config,
+            data_root: PathBuf::from(DEFAULT_DATA_ROOT),
+            client,
+        }
+    }
+
+    pub fn with_data_root(mut self, data_root: PathBuf) -> Self {
+        self.data_root = data_root;
+        self
+    }
+
+    /// The files of the dataset along with their as-of dates, i.e. all files matching the `{as_of_date}`
+    /// placeholder of the file name, or the single file of the source otherwise.
+    fn source_files(&self) -> Result<Vec<(PathBuf, Option<NaiveDate>)>, AppError> {
+        let path = self.data_root.join(&self.config.source);
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (prefix, suffix) = match file_name.split_once(AS_OF_DATE_PLACEHOLDER) {
+            Some(affixes) => affixes,
+            None => return Ok(vec![(path, None)]),
+        };
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let mut files = Vec::new();
+        for entry in read_dir(dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            let as_of_date = name
+                .strip_prefix(prefix)
+                .and_then(|name| name.strip_suffix(suffix))
+                .and_then(|date| NaiveDate::parse_from_str(date, AS_OF_DATE_FORMAT).ok());
+            if let Some(as_of_date) = as_of_date {
+                files.push((dir.join(&name), Some(as_of_date)));
+            }
+        }
+        files.sort_by_key(|(_, as_of_date)| *as_of_date);
+        Ok(files)
+    }
+
+    /// Loads the records of all sources of the dataset, an url with the `{as_of_date}` placeholder
+    /// is requested as of today.
+    pub fn load(&self, today: &NaiveDate) -> Result<Dataset, AppError> {
+        let sources: Vec<(String, Option<NaiveDate>)> = if self.config.is_url() {
+            let url = self.config.source.replace(
+                AS_OF_DATE_PLACEHOLDER,
+                &today.format(AS_OF_DATE_FORMAT).to_string(),
+            );
+            let as_of_date = self
+                .config
+                .source
+                .contains(AS_OF_DATE_PLACEHOLDER)
+                .then_some(*today);
+            vec![(self.client.get_text(&url, &[], &[])?, as_of_date)]
+        } else {
+            let mut sources = Vec::new();
+            for (path, as_of_date) in self.source_files()? {
+                info!("Reading dataset file {:?}", path);
+                sources.push((std::fs::read_to_string(&path)?, as_of_date));
+            }
+            sources
+        };
+
+        let mut records = Vec::new();
+        for (content, as_of_date) in sources {
+            records.extend(match self.config.format() {
+                DatasetFormat::Csv => parse_csv(content.into_bytes(), &self.config, as_of_date)?,
+                DatasetFormat::Json => parse_json(&content, &self.config, as_of_date)?,
+            });
+        }
+        Ok(Dataset {
+            name: self.config.name.clone(),
+            value_columns: self.config.value_columns.clone(),
+            records,
+        })
     }
 }
 
 mod utils {
+    use super::Compression;
+    use flate2::read::GzDecoder;
+    use flate2::write::GzEncoder;
     use log::{error, trace};
-    use std::fs::{read_dir, read_to_string, File};
-    use std::io::Write;
+    use sha2::{Digest, Sha256};
+    use std::collections::{HashMap, HashSet};
+    use std::fs::{read_dir, read_to_string, rename, File, OpenOptions};
+    use std::io::{BufWriter, Read, Write};
     use std::path::Path;
     use std::path::PathBuf;
     use std::result::Result;
 
     use crate::errors::AppError;
 
-    /// Save the serialized content to the specified file.
+    /// The file of the content hashes of the saved files within a directory.
+    const HASH_INDEX: &str = ".hashes";
+
+    /// Save the serialized content to the specified (json) file, compressed as requested.
+    /// The content is written to a temporary file first and renamed, such that an interrupted run
+    /// never leaves a truncated file behind.
     pub fn save_file<T: serde::Serialize + std::fmt::Debug>(
         file_path: &Path,
         content: T,
+        compression: Compression,
     ) -> Result<(), AppError> {
-        let mut output = File::create(file_path)?;
-        let serialized: String = serde_json::to_string(&content)?;
-        write!(output, "{}", serialized)?;
+        let mut final_path = file_path.as_os_str().to_owned();
+        final_path.push(compression.extension());
+        let final_path = PathBuf::from(final_path);
+        let mut tmp_path = final_path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let serialized: Vec<u8> = serde_json::to_vec(&content)?;
+        let file = File::create(&tmp_path)?;
+        let file = match compression {
+            Compression::None => {
+                let mut output = BufWriter::new(file);
+                output.write_all(&serialized)?;
+                output.into_inner().map_err(|e| e.into_error())?
+            }
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(file, flate2::Compression::default());
+                encoder.write_all(&serialized)?;
+                encoder.finish()?
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(file, 0)?;
+                encoder.write_all(&serialized)?;
+                encoder.finish()?
+            }
+        };
+        file.sync_all()?;
+        rename(&tmp_path, &final_path)?;
         Ok(())
     }
 
+    /// The stem of a (possibly compressed) json file, e.g. '2022-07-25' of '2022-07-25.json.gz'.
+    pub fn json_file_stem(file_name: &str) -> Option<&str> {
+        Compression::ALL
+            .iter()
+            .find_map(|c| file_name.strip_suffix(c.extension())?.strip_suffix(".json"))
+    }
+
+    /// The path of the (json) file within the directory in any compression, if it exists.
+    pub fn find_file(dir: &Path, file_name: &str) -> Option<PathBuf> {
+        Compression::ALL
+            .iter()
+            .map(|c| dir.join(format!("{}{}", file_name, c.extension())))
+            .find(|path| path.exists())
+    }
+
+    /// The hex encoded SHA-256 hash of the serialized content.
+    pub fn content_hash<T: serde::Serialize>(content: &T) -> Result<String, AppError> {
+        let serialized = serde_json::to_vec(content)?;
+        Ok(format!("{:x}", Sha256::digest(&serialized)))
+    }
+
+    /// The content hashes of the files saved to a directory, stored line by line as '<hash> <file name>'.
+    /// The names of skipped duplicates are stored as well, the first file name of a hash is the saved file.
+    pub struct HashIndex {
+        path: PathBuf,
+        file_names: HashMap<String, String>,
+        recorded: HashSet<String>,
+    }
+
+    impl HashIndex {
+        pub fn load(dir: &Path) -> Result<Self, AppError> {
+            let path = dir.join(HASH_INDEX);
+            let mut file_names = HashMap::new();
+            let mut recorded = HashSet::new();
+            if path.exists() {
+                for line in read_to_string(&path)?.lines() {
+                    if let Some((hash, file_name)) = line.split_once(' ') {
+                        file_names
+                            .entry(hash.to_string())
+                            .or_insert_with(|| file_name.to_string());
+                        recorded.insert(file_name.to_string());
+                    }
+                }
+            }
+            Ok(Self {
+                path,
+                file_names,
+                recorded,
+            })
+        }
+
+        /// The name of the saved file with the hash.
+        pub fn file_name(&self, hash: &str) -> Option<&String> {
+            self.file_names.get(hash)
+        }
+
+        /// Whether the file name was saved or skipped as a duplicate.
+        pub fn contains(&self, file_name: &str) -> bool {
+            self.recorded.contains(file_name)
+        }
+
+        pub fn insert(&mut self, hash: &str, file_name: &str) -> Result<(), AppError> {
+            let mut index = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            writeln!(index, "{} {}", hash, file_name)?;
+            self.file_names
+                .entry(hash.to_string())
+                .or_insert_with(|| file_name.to_string());
+            self.recorded.insert(file_name.to_string());
+            Ok(())
+        }
+    }
+
     fn read_file<T: serde::de::DeserializeOwned>(path: &PathBuf) -> Result<T, AppError> {
-        let content = read_to_string(&path)?;
-        let t: T = serde_json::from_str(&content).map_err(|e| {
+        let file_name = path.to_string_lossy();
+        let mut content = String::new();
+        if file_name.ends_with(Compression::Gzip.extension()) {
+            GzDecoder::new(File::open(path)?).read_to_string(&mut content)?;
+        } else if file_name.ends_with(Compression::Zstd.extension()) {
+            zstd::Decoder::new(File::open(path)?)?.read_to_string(&mut content)?;
+        } else {
+            content = read_to_string(path)?;
+        }
+        let t: T = serde_json::from_str(&content).inspect_err(|_| {
             error!("cannot deserialize file '{:?}'", path);
-            e
         })?;
         Ok(t)
     }
 
+    /// Reads all (possibly compressed) json files of the directory,
+    /// skipping sub-directories such as the invalid responses and other files such as the hash index.
     pub fn read_files<T: serde::de::DeserializeOwned>(dir: &Path) -> Result<Vec<T>, AppError> {
         let mut files: Vec<T> = Vec::new();
         for entry in read_dir(dir)? {
             let file = entry?;
             let path = file.path();
+            let is_json = json_file_stem(&file.file_name().to_string_lossy()).is_some();
+            if !path.is_file() || !is_json {
+                trace!("skipping '{:?}'", path);
+                continue;
+            }
             trace!("reading file '{:?}", path);
             let t: T = read_file(&path)?;
             files.push(t);
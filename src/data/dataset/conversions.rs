@@ -0,0 +1,208 @@
+use crate::data::dataset::models::{Dataset, DatasetConfig, DatasetRecord};
+use crate::data::models::{ForecastSet, TimeSeries, TimeSeriesPoint};
+use crate::errors::AppError;
+use chrono::NaiveDate;
+use polars::prelude::{CsvReader, DataFrame, DataType, SerReader};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+/// Parses the records of a CSV source, with the as-of date of the source unless given by the as-of column.
+pub fn parse_csv(
+    content: Vec<u8>,
+    config: &DatasetConfig,
+    as_of_date: Option<NaiveDate>,
+) -> Result<Vec<DatasetRecord>, AppError> {
+    let df = CsvReader::new(Cursor::new(content))
+        .has_header(true)
+        .finish()?;
+    let dates = date_column(&df, config, &config.date_column)?;
+    let as_of_dates = match &config.as_of_column {
+        Some(as_of_column) => date_column(&df, config, as_of_column)?,
+        None => vec![as_of_date; df.height()],
+    };
+
+    let mut columns = Vec::with_capacity(config.value_columns.len());
+    for value_column in config.value_columns.iter() {
+        let values = column(&df, value_column)?.cast(&DataType::Float32)?;
+        columns.push(values.f32()?.into_iter().collect::<Vec<Option<f32>>>());
+    }
+
+    let records = dates
+        .into_iter()
+        .zip(as_of_dates)
+        .enumerate()
+        .flat_map(|(idx, (date, as_of_date))| {
+            Some(DatasetRecord {
+                as_of_date,
+                date: date?,
+                values: columns.iter().map(|values| values[idx]).collect(),
+            })
+        })
+        .collect();
+    Ok(records)
+}
+
+fn column<'a>(df: &'a DataFrame, name: &str) -> Result<&'a polars::prelude::Series, AppError> {
+    df.column(name)
+        .map_err(|_| AppError::SetupDataset(format!("missing column '{}'", name)))
+}
+
+/// The dates of the column, none for missing or unparsable dates.
+fn date_column(
+    df: &DataFrame,
+    config: &DatasetConfig,
+    name: &str,
+) -> Result<Vec<Option<NaiveDate>>, AppError> {
+    let dates = column(df, name)?.cast(&DataType::Utf8)?;
+    let dates = dates
+        .utf8()?
+        .into_iter()
+        .map(|date| config.parse_date(date?))
+        .collect();
+    Ok(dates)
+}
+
+/// Parses the records of a JSON source, i.e. the objects of the array at the `records` pointer,
+/// with the as-of date of the source unless given by the as-of column.
+pub fn parse_json(
+    content: &str,
+    config: &DatasetConfig,
+    as_of_date: Option<NaiveDate>,
+) -> Result<Vec<DatasetRecord>, AppError> {
+    let json: Value = serde_json::from_str(content)?;
+    let pointer = config.records.as_deref().unwrap_or("");
+    let records = json
+        .pointer(pointer)
+        .and_then(|records| records.as_array())
+        .ok_or_else(|| {
+            AppError::SetupDataset(format!(
+                "no array of records at '{}' of {}",
+                pointer, config
+            ))
+        })?;
+
+    let date_of =
+        |record: &Value, name: &str| record[name].as_str().and_then(|d| config.parse_date(d));
+    let records = records
+        .iter()
+        .flat_map(|record| {
+            let as_of_date = match &config.as_of_column {
+                Some(as_of_column) => Some(date_of(record, as_of_column)?),
+                None => as_of_date,
+            };
+            Some(DatasetRecord {
+                as_of_date,
+                date: date_of(record, &config.date_column)?,
+                values: config
+                    .value_columns
+                    .iter()
+                    .map(|name| json_value(&record[name]))
+                    .collect(),
+            })
+        })
+        .collect();
+    Ok(records)
+}
+
+/// The value of a number or a numeric string, none otherwise.
+fn json_value(value: &Value) -> Option<f32> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|v| v as f32),
+        Value::String(string) => string.trim().parse::<f32>().ok(),
+        _ => None,
+    }
+}
+
+/// The vintages of each value column, i.e. the curves keyed by their as-of date,
+/// empty if the dataset has no as-of dates.
+pub fn dataset_vintages(dataset: &Dataset) -> Vec<(String, ForecastSet)> {
+    if dataset.records.iter().all(|r| r.as_of_date.is_none()) {
+        return Vec::new();
+    }
+    dataset
+        .value_columns
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            let mut curves: BTreeMap<NaiveDate, Vec<TimeSeriesPoint>> = BTreeMap::new();
+            for record in dataset.records.iter() {
+                if let (Some(as_of_date), Some(value)) = (record.as_of_date, record.values[idx]) {
+                    curves.entry(as_of_date).or_default().push(TimeSeriesPoint {
+                        date: record.date,
+                        value,
+                    });
+                }
+            }
+            let vintages: ForecastSet = curves
+                .into_iter()
+                .map(|(as_of_date, points)| (as_of_date, TimeSeries::new(points)))
+                .collect();
+            (name.clone(), vintages)
+        })
+        .collect()
+}
+
+/// The series of each value column, i.e. the latest value per date for datasets with as-of dates.
+pub fn dataset_series(dataset: &Dataset) -> Vec<(String, TimeSeries)> {
+    let vintages = dataset_vintages(dataset);
+    if !vintages.is_empty() {
+        return vintages
+            .into_iter()
+            .map(|(name, vintages)| (name, vintages.latest()))
+            .collect();
+    }
+    dataset
+        .value_columns
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            let points = dataset
+                .records
+                .iter()
+                .flat_map(|record| {
+                    Some(TimeSeriesPoint {
+                        date: record.date,
+                        value: record.values[idx]?,
+                    })
+                })
+                .collect();
+            (name.clone(), TimeSeries::new(points))
+        })
+        .collect()
+}
+
+pub fn print_dataset_summary(dataset: &Dataset, series: &[(String, TimeSeries)]) {
+    println!(
+        "Dataset {} with {} records",
+        dataset.name,
+        dataset.records.len()
+    );
+    println!(
+        "{:>20} | {:>10} | {:>10} | {:>6} | {:>10} | {:>10} | {:>10}",
+        "column", "from", "to", "count", "min", "mean", "max"
+    );
+    for (name, ts) in series.iter() {
+        let (first, last) = match (ts.first(), ts.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => {
+                println!("{:>20} | no values", name);
+                continue;
+            }
+        };
+        let values: Vec<f32> = ts.iter().map(|tsp| tsp.value).collect();
+        let min = values.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        println!(
+            "{:>20} | {:>10} | {:>10} | {:>6} | {:>10.2} | {:>10.2} | {:>10.2}",
+            name,
+            first.date,
+            last.date,
+            values.len(),
+            min,
+            mean,
+            max
+        );
+    }
+}
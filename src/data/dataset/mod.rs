@@ -0,0 +1,7 @@
+mod conversions;
+pub mod models;
+
+pub use conversions::{
+    dataset_series, dataset_vintages, parse_csv, parse_json, print_dataset_summary,
+};
+pub use models::{Dataset, DatasetConfig, DatasetFormat};
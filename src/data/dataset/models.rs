@@ -0,0 +1,96 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Deserialize;
+use std::fmt;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+/// The placeholder of the as-of date within the source of a dataset, e.g. `vintages/{as_of_date}.csv`.
+pub const AS_OF_DATE_PLACEHOLDER: &str = "{as_of_date}";
+/// The format of the as-of dates within the sources.
+pub const AS_OF_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The format of a dataset, by default derived from the extension of its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    Csv,
+    Json,
+}
+
+fn default_date_format() -> String {
+    DEFAULT_DATE_FORMAT.to_string()
+}
+
+/// A tabular dataset configured as `[[datasets]]` in the `config.toml`, e.g.
+/// ```toml
+/// [[datasets]]
+/// name="zurich_observations"
+/// source="datasets/zurich_observations.csv"
+/// date_column="date"
+/// value_columns=["max_temp", "min_temp"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatasetConfig {
+    pub name: String,
+    /// The path of the file (relative to the data root unless absolute) or an http(s) url.
+    /// The placeholder `{as_of_date}` within a path matches the files of all as-of dates,
+    /// within an url it is replaced by the current date.
+    pub source: String,
+    /// The format of the source, by its extension if not set (CSV unless `.json`)
+    pub format: Option<DatasetFormat>,
+    pub date_column: String,
+    pub value_columns: Vec<String>,
+    /// The (chrono) format of the dates, date-times are truncated to their date
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// The column of the as-of dates of the values, e.g. of forecasts or revised figures
+    pub as_of_column: Option<String>,
+    /// The JSON pointer to the array of records within a JSON source, e.g. `/data`, the root by default
+    pub records: Option<String>,
+}
+
+impl DatasetConfig {
+    pub fn format(&self) -> DatasetFormat {
+        match self.format {
+            Some(format) => format,
+            None if self.source.ends_with(".json") => DatasetFormat::Json,
+            None => DatasetFormat::Csv,
+        }
+    }
+
+    pub fn is_url(&self) -> bool {
+        self.source.starts_with("http://") || self.source.starts_with("https://")
+    }
+
+    pub fn parse_date(&self, date: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(date.trim(), &self.date_format)
+            .ok()
+            .or_else(|| {
+                NaiveDateTime::parse_from_str(date.trim(), &self.date_format)
+                    .ok()
+                    .map(|date_time| date_time.date())
+            })
+    }
+}
+
+impl fmt::Display for DatasetConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// A record of a dataset, i.e. a row of a CSV resp. an object of the JSON records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetRecord {
+    /// The as-of date of the values, by the as-of column or the source, if any
+    pub as_of_date: Option<NaiveDate>,
+    pub date: NaiveDate,
+    /// The values of the value columns, none if missing or not numeric
+    pub values: Vec<Option<f32>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub name: String,
+    pub value_columns: Vec<String>,
+    pub records: Vec<DatasetRecord>,
+}
@@ -1,129 +1,374 @@
-use crate::data::football::models::FootballSeasonResults;
-use polars::df;
+use crate::data::football::football::FootballLeague;
+use crate::data::football::metrics::{FootballMetric, PlayerSeasonTotals};
+use crate::data::football::models::{FootballPlayer, FootballSeasonResults, PlayerStats};
+use crate::errors::AppError;
+use log::debug;
 use polars::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::result::Result;
 
-pub fn column_vec(df: &polars::prelude::DataFrame, col_name: &str) -> Vec<Option<f32>> {
-    let chunked_arr = df.column(col_name).unwrap().f32().unwrap();
-    Vec::from(chunked_arr)
+/// Extracts the values of a numeric column, casting them to the requested type.
+pub fn column_values<T: PolarsNumericType>(
+    df: &DataFrame,
+    col_name: &str,
+) -> Result<Vec<Option<T::Native>>, AppError> {
+    let series = df.column(col_name)?.cast(&T::get_dtype())?;
+    let values = series.unpack::<T>()?.into_iter().collect();
+    Ok(values)
 }
 
-pub fn join_players(
-    df1: polars::prelude::DataFrame,
-    df2: &polars::prelude::DataFrame,
-) -> polars::prelude::DataFrame {
-    df1.join(
-        df2,
-        ["season"],
-        ["season"],
-        JoinType::Inner,
-        Some(".other".to_string()),
-    )
-    .unwrap()
+/// The axis along which the careers of players are aligned for comparisons.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum CareerAxis {
+    Season,
+    Age,
 }
 
-// TODO: there must be a nicer way!
-pub fn filter_players(
-    df: &polars::prelude::DataFrame,
-    player_id: i32,
-) -> polars::prelude::DataFrame {
-    let filtered_ids: Vec<i32> = df
-        .column("player_id")
-        .unwrap()
-        .i32()
-        .unwrap()
+impl CareerAxis {
+    pub fn column_name(&self) -> &'static str {
+        match self {
+            CareerAxis::Season => "season",
+            CareerAxis::Age => "age",
+        }
+    }
+}
+
+/// Selects the players and (optionally) a range of seasons, bounds inclusive.
+#[derive(Debug, Clone, Default)]
+pub struct PlayerFilter {
+    pub player_ids: Vec<i32>,
+    pub from_season: Option<i32>,
+    pub to_season: Option<i32>,
+}
+
+impl PlayerFilter {
+    fn expr(&self) -> Expr {
+        let mut predicate = self
+            .player_ids
+            .iter()
+            .map(|id| col("player_id").eq(lit(*id)))
+            .reduce(|acc, e| acc.or(e))
+            .unwrap_or_else(|| lit(true));
+
+        if let Some(from_season) = self.from_season {
+            predicate = predicate.and(col("season").gt_eq(lit(from_season)));
+        }
+        if let Some(to_season) = self.to_season {
+            predicate = predicate.and(col("season").lt_eq(lit(to_season)));
+        }
+        predicate
+    }
+}
+
+pub fn filter_players(df: &DataFrame, filter: &PlayerFilter) -> Result<DataFrame, AppError> {
+    let filtered = df.clone().lazy().filter(filter.expr()).collect()?;
+    Ok(filtered)
+}
+
+/// The names of the players contained in the data frame, keyed by player id.
+pub fn player_names(df: &DataFrame) -> Result<HashMap<i32, String>, AppError> {
+    let ids = df.column("player_id")?.i32()?;
+    let names = df.column("player_name")?.utf8()?;
+    let names_by_id = ids
         .into_iter()
-        .map(|id| match id {
-            Some(i) if i == player_id => player_id,
-            _ => -1,
-        })
+        .zip(names)
+        .flat_map(|(id, name)| id.zip(name.map(|n| n.to_string())))
         .collect();
+    Ok(names_by_id)
+}
 
-    let s0 = Series::new("player_id", &filtered_ids);
-
-    df.filter(&df.column("player_id").unwrap().equal(&s0).unwrap())
-        .unwrap()
+/// The column name of a metric of a player in the pivoted data frame.
+pub fn pivot_column(metric_name: &str, player_id: i32) -> String {
+    format!("{}.{}", metric_name, player_id)
 }
 
-// TODO: add more metrics and stats
-pub fn convert_data_frame(
-    season_results: Vec<FootballSeasonResults>,
-) -> polars::prelude::DataFrame {
-    let mut player_ids = Vec::new();
-    let mut season = Vec::new();
-    let mut goals = Vec::new();
-    let mut minutes = Vec::new();
-    let mut cards_weighted = Vec::new();
-    let mut passes_total = Vec::new();
-    let mut goals_per_minute: Vec<f32> = Vec::new();
-    let mut fairness_per_minute: Vec<f32> = Vec::new();
-    let mut passes_per_minute: Vec<f32> = Vec::new();
-
-    let add_per_minute = |(container, v, nr_mins): (&mut Vec<f32>, i32, i32)| {
-        container.push(if nr_mins == 0 {
-            0.0
-        } else {
-            v as f32 / nr_mins as f32
+/// Pivots the metrics of the players into one column per metric and player (see `pivot_column`),
+/// joined on the career axis, i.e. only seasons resp. ages common to all players are kept.
+/// Statistics split by team are aggregated per player and season first, see `aggregate_player_seasons`.
+pub fn pivot_players(
+    df: &DataFrame,
+    axis: CareerAxis,
+    player_ids: &[i32],
+    metric_names: &[&str],
+) -> Result<DataFrame, AppError> {
+    let axis_col = axis.column_name();
+    let player_seasons = aggregate_player_seasons(df);
+
+    let pivoted = player_ids
+        .iter()
+        .map(|&player_id| {
+            let mut selection = vec![col(axis_col)];
+            selection.extend(
+                metric_names
+                    .iter()
+                    .map(|name| col(name).alias(&pivot_column(name, player_id))),
+            );
+            player_seasons
+                .clone()
+                .filter(col("player_id").eq(lit(player_id)))
+                .select(selection)
         })
-    };
-
-    for season_result in season_results {
-        for player_results in season_result.player_results {
-            player_ids.push(player_results.player.id);
-            player_results.statistics.first().map(|res| {
-                let gs = res
-                    .goals
-                    .as_ref()
-                    .and_then(|r| r.total)
-                    .unwrap_or_else(|| 0);
-
-                let mins = res
-                    .games
-                    .as_ref()
-                    .and_then(|r| r.minutes)
-                    .unwrap_or_else(|| 0);
-
-                let cards_total_weighted = res
-                    .cards
-                    .as_ref()
-                    .map(|cards| {
-                        cards.red.unwrap_or(0) * 3
-                            + cards.yellowred.unwrap_or(0) * 2
-                            + cards.yellow.unwrap_or(0)
-                    })
-                    .unwrap_or_else(|| 0);
-
-                let passes = res
-                    .passes
-                    .as_ref()
-                    .and_then(|p| p.total)
-                    .unwrap_or_else(|| 0);
-
-                minutes.push(mins);
-                goals.push(gs);
-                passes_total.push(passes);
-                cards_weighted.push(cards_total_weighted);
-
-                add_per_minute((&mut goals_per_minute, gs, mins));
-                add_per_minute((&mut fairness_per_minute, cards_total_weighted, mins));
-                add_per_minute((&mut passes_per_minute, passes, mins));
-            });
-            season.push(season_result.season as f32);
+        .reduce(|acc, lf| acc.join(lf, [col(axis_col)], [col(axis_col)], JoinType::Inner))
+        .map(|lf| lf.sort(axis_col, Default::default()).collect())
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(pivoted)
+}
+
+/// Defines how the statistics entries of a player within a season are combined.
+/// Players appear with several entries if they played for several teams or in several leagues.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum StatisticsGrouping {
+    /// One row per player and season, aggregated over all teams and leagues
+    Player,
+    /// One row per player, season, team and league
+    Team,
+}
+
+/// A single statistics entry of a player within a season.
+struct StatisticsEntry {
+    season: i32,
+    player_id: i32,
+    player_name: String,
+    age: Option<i32>,
+    team_id: Option<i32>,
+    team: String,
+    league: String,
+    totals: PlayerSeasonTotals,
+}
+
+impl StatisticsEntry {
+    fn new(
+        league: &FootballLeague,
+        season: i32,
+        player: &FootballPlayer,
+        stats: &PlayerStats,
+    ) -> Self {
+        Self {
+            season,
+            player_id: player.id,
+            player_name: player.display_name(),
+            age: player.age_in_season(season),
+            team_id: stats.team.as_ref().and_then(|t| t.id),
+            team: stats
+                .team
+                .as_ref()
+                .and_then(|t| t.name.clone())
+                .unwrap_or_default(),
+            league: stats
+                .league
+                .as_ref()
+                .and_then(|l| l.name.clone())
+                .unwrap_or_else(|| league.name.clone()),
+            totals: PlayerSeasonTotals::from(stats),
+        }
+    }
+}
+
+/// Flattens the season results of the leagues into the statistics entries.
+/// Players without any statistics within a season are skipped, entries contained in several
+/// leagues' results (e.g. of players transferred within a season) are only kept once.
+fn collect_entries(
+    results_by_league: Vec<(FootballLeague, Vec<FootballSeasonResults>)>,
+) -> Vec<StatisticsEntry> {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (league, season_results) in results_by_league {
+        for season_result in season_results {
+            let season = season_result.season;
+            for player_results in season_result.player_results {
+                let player = &player_results.player;
+                if player_results.statistics.is_empty() {
+                    debug!(
+                        "no statistics for player {} in season {}",
+                        player.id, season
+                    );
+                }
+
+                for stats in player_results.statistics.iter() {
+                    let entry = StatisticsEntry::new(&league, season, player, stats);
+                    let key = (
+                        entry.season,
+                        entry.player_id,
+                        entry.team.clone(),
+                        entry.league.clone(),
+                    );
+                    if seen.insert(key) {
+                        entries.push(entry);
+                    }
+                }
+            }
         }
     }
 
-    let data_frame = df![
-        "season" => &season,
-        "player_id" => &player_ids,
-        "goals" => &goals,
-        "minutes" => &minutes,
-        "passes" => &passes_total,
-        "cards_weighted" => &cards_weighted,
-        "goals_per_minute" => &goals_per_minute,
-        "fairness_per_minute" => &fairness_per_minute,
-        "passes_per_minute" => &passes_per_minute
+    entries
+}
+
+/// Joins the distinct team resp. league names of each player within a season, e.g. 'Juventus / Real Madrid'.
+fn join_names_per_player_season(entries: &mut [StatisticsEntry]) {
+    let mut teams: BTreeMap<(i32, i32), Vec<String>> = BTreeMap::new();
+    let mut leagues: BTreeMap<(i32, i32), Vec<String>> = BTreeMap::new();
+
+    for entry in entries.iter() {
+        let key = (entry.season, entry.player_id);
+        for (names, name) in [
+            (teams.entry(key).or_default(), &entry.team),
+            (leagues.entry(key).or_default(), &entry.league),
+        ] {
+            if !name.is_empty() && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    for entry in entries.iter_mut() {
+        let key = (entry.season, entry.player_id);
+        entry.team = teams[&key].join(" / ");
+        entry.league = leagues[&key].join(" / ");
+    }
+}
+
+fn entries_data_frame(entries: &[StatisticsEntry]) -> Result<DataFrame, AppError> {
+    let mut columns = vec![
+        Series::new(
+            "season",
+            entries.iter().map(|e| e.season).collect::<Vec<i32>>(),
+        ),
+        Series::new(
+            "age",
+            entries.iter().map(|e| e.age).collect::<Vec<Option<i32>>>(),
+        ),
+        Series::new(
+            "player_id",
+            entries.iter().map(|e| e.player_id).collect::<Vec<i32>>(),
+        ),
+        Series::new(
+            "player_name",
+            entries
+                .iter()
+                .map(|e| e.player_name.clone())
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "team_id",
+            entries
+                .iter()
+                .map(|e| e.team_id)
+                .collect::<Vec<Option<i32>>>(),
+        ),
+        Series::new(
+            "team",
+            entries
+                .iter()
+                .map(|e| e.team.clone())
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "league",
+            entries
+                .iter()
+                .map(|e| e.league.clone())
+                .collect::<Vec<String>>(),
+        ),
+    ];
+
+    let totals: [fn(&PlayerSeasonTotals) -> i32; 9] = [
+        |t| t.minutes,
+        |t| t.appearances,
+        |t| t.goals,
+        |t| t.goals_conceded,
+        |t| t.passes,
+        |t| t.passes_accurate,
+        |t| t.duels,
+        |t| t.duels_won,
+        |t| t.cards_weighted,
     ];
-    // let season_series = Series::new("season", &season);
-    // let player_ids_series = Series::new("player_ids", &player_ids);
-    // let df = DataFrame::new(vec![season_series, player_ids_series]);
-    data_frame.unwrap()
+    for (name, total) in FootballMetric::TOTAL_COLUMNS.iter().zip(totals.iter()) {
+        let values: Vec<i32> = entries.iter().map(|e| total(&e.totals)).collect();
+        columns.push(Series::new(name, values));
+    }
+
+    Ok(DataFrame::new(columns)?)
+}
+
+/// Converts the season results of the leagues into a data frame with one row per player and season
+/// (merged over all leagues), or per player, season, team and league, depending on the grouping.
+/// Besides the raw totals, the data frame contains a column for each registered metric.
+pub fn convert_data_frame(
+    results_by_league: Vec<(FootballLeague, Vec<FootballSeasonResults>)>,
+    grouping: StatisticsGrouping,
+) -> Result<DataFrame, AppError> {
+    let mut entries = collect_entries(results_by_league);
+
+    let mut group_keys = vec![col("season"), col("player_id")];
+    match grouping {
+        StatisticsGrouping::Player => join_names_per_player_season(&mut entries),
+        StatisticsGrouping::Team => group_keys.extend([col("team_id"), col("team"), col("league")]),
+    }
+
+    let mut aggregations = vec![col("age").first(), col("player_name").first()];
+    if let StatisticsGrouping::Player = grouping {
+        aggregations.extend([
+            col("team_id").first(),
+            col("team").first(),
+            col("league").first(),
+        ]);
+    }
+    aggregations.extend(FootballMetric::TOTAL_COLUMNS.iter().map(|c| col(c).sum()));
+
+    let df = entries_data_frame(&entries)?
+        .lazy()
+        .groupby(group_keys)
+        .agg(aggregations)
+        .with_columns(
+            FootballMetric::ALL
+                .iter()
+                .map(|m| m.expr())
+                .collect::<Vec<Expr>>(),
+        )
+        .sort_by_exprs(
+            vec![col("season"), col("player_id")],
+            vec![false, false],
+            false,
+        )
+        .collect()?;
+
+    Ok(df)
+}
+
+/// Aggregates a data frame into one row per player and season, e.g. the statistics split by team
+/// (see `StatisticsGrouping::Team`); a (player-season) data frame is kept as is.
+/// The metrics are recomputed from the season totals.
+pub fn aggregate_player_seasons(df: &DataFrame) -> LazyFrame {
+    let mut aggregations = vec![col("age").first(), col("player_name").first()];
+    aggregations.extend(FootballMetric::TOTAL_COLUMNS.iter().map(|c| col(c).sum()));
+    df.clone()
+        .lazy()
+        .groupby([col("season"), col("player_id")])
+        .agg(aggregations)
+        .with_columns(
+            FootballMetric::ALL
+                .iter()
+                .map(|m| m.expr())
+                .collect::<Vec<Expr>>(),
+        )
+}
+
+/// Aggregates a (player-season) data frame over all seasons into one row per player.
+/// The metrics are recomputed from the career totals rather than averaging the season values.
+pub fn aggregate_careers(df: &DataFrame) -> LazyFrame {
+    let mut aggregations = vec![col("player_name").first()];
+    aggregations.extend(FootballMetric::TOTAL_COLUMNS.iter().map(|c| col(c).sum()));
+    df.clone()
+        .lazy()
+        .groupby([col("player_id")])
+        .agg(aggregations)
+        .with_columns(
+            FootballMetric::ALL
+                .iter()
+                .map(|m| m.expr())
+                .collect::<Vec<Expr>>(),
+        )
 }
@@ -1,9 +1,39 @@
+/// The api-sports endpoint of the player statistics per league and season.
+pub const API_SPORTS_PLAYERS_URL: &str = "https://v3.football.api-sports.io/players";
+
+/// A football league as identified by api-sports, e.g. id 140 for the spanish 'La Liga'.
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct FootballLeague {
+    pub id: i32,
     pub country: String,
+    pub name: String,
+}
+
+/// The top leagues by country, i.e. the leagues of the legacy `football_country` setting.
+const TOP_LEAGUES: [(i32, &str, &str); 5] = [
+    (140, "Spain", "La Liga"),
+    (135, "Italy", "Serie A"),
+    (39, "England", "Premier League"),
+    (78, "Germany", "Bundesliga"),
+    (61, "France", "Ligue 1"),
+];
+
+impl FootballLeague {
+    /// The top league of the country, if known.
+    pub fn top_league(country: &str) -> Option<Self> {
+        TOP_LEAGUES
+            .iter()
+            .find(|(_, c, _)| c.eq_ignore_ascii_case(country))
+            .map(|(id, country, name)| Self {
+                id: *id,
+                country: country.to_string(),
+                name: name.to_string(),
+            })
+    }
 }
 
 impl std::fmt::Display for FootballLeague {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.country)
+        write!(f, "{}__{}", self.country.to_lowercase(), self.id)
     }
 }
@@ -0,0 +1,259 @@
+use crate::data::football::conversions::{aggregate_careers, aggregate_player_seasons};
+use crate::data::football::metrics::FootballMetric;
+use crate::errors::AppError;
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::result::Result;
+
+/// Whether the players are ranked within each season or over all loaded seasons.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum RankingScope {
+    Season,
+    Career,
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaderboardEntry {
+    /// The season of the ranking, `None` if ranked over all seasons
+    pub season: Option<i32>,
+    pub rank: usize,
+    pub player_id: i32,
+    pub player_name: String,
+    pub minutes: i32,
+    pub value: f32,
+}
+
+/// Ranks the players of a (player-season) data frame by the metric.
+/// Players with fewer minutes than `min_minutes` within the season (resp. career) are excluded.
+/// Statistics split by team are aggregated per player and season first, i.e. each player is ranked once.
+/// Ties are ranked by the player id, so that the ranking is deterministic.
+pub fn rank_players(
+    df: &DataFrame,
+    metric: FootballMetric,
+    min_minutes: i32,
+    scope: RankingScope,
+) -> Result<Vec<LeaderboardEntry>, AppError> {
+    let lf = match scope {
+        RankingScope::Season => aggregate_player_seasons(df),
+        RankingScope::Career => aggregate_careers(df).with_column(lit(0).alias("season")),
+    };
+
+    let ranked = lf
+        .filter(
+            col("minutes")
+                .gt_eq(lit(min_minutes))
+                .and(col(metric.name()).is_not_null()),
+        )
+        .select([
+            col("season").cast(DataType::Int32),
+            col("player_id"),
+            col("player_name"),
+            col("minutes").cast(DataType::Int32),
+            col(metric.name()).cast(DataType::Float32),
+        ])
+        .sort_by_exprs(
+            vec![col("season"), col(metric.name()), col("player_id")],
+            vec![false, metric.higher_is_better(), false],
+            false,
+        )
+        .collect()?;
+
+    let seasons = ranked.column("season")?.i32()?;
+    let player_ids = ranked.column("player_id")?.i32()?;
+    let player_names = ranked.column("player_name")?.utf8()?;
+    let minutes = ranked.column("minutes")?.i32()?;
+    let values = ranked.column(metric.name())?.f32()?;
+
+    let mut entries: Vec<LeaderboardEntry> = Vec::with_capacity(ranked.height());
+    for idx in 0..ranked.height() {
+        let season = seasons.get(idx).unwrap_or_default();
+        let rank = match entries.last() {
+            Some(previous) if previous.season == Some(season) => previous.rank + 1,
+            _ => 1,
+        };
+        entries.push(LeaderboardEntry {
+            season: Some(season),
+            rank,
+            player_id: player_ids.get(idx).unwrap_or_default(),
+            player_name: player_names.get(idx).unwrap_or_default().to_string(),
+            minutes: minutes.get(idx).unwrap_or_default(),
+            value: values.get(idx).unwrap_or_default(),
+        });
+    }
+
+    if let RankingScope::Career = scope {
+        entries.iter_mut().for_each(|e| e.season = None);
+    }
+
+    Ok(entries)
+}
+
+/// Keeps the top N entries of each season.
+pub fn top_entries(entries: &[LeaderboardEntry], top_n: usize) -> Vec<LeaderboardEntry> {
+    entries
+        .iter()
+        .filter(|e| e.rank <= top_n)
+        .cloned()
+        .collect()
+}
+
+/// The rank trajectories over the seasons of all players which made it into the top N in any season.
+pub fn rank_trajectories(
+    entries: &[LeaderboardEntry],
+    top_n: usize,
+) -> Vec<(String, Vec<(i32, usize)>)> {
+    let mut trajectories: BTreeMap<i32, (String, Vec<(i32, usize)>)> = BTreeMap::new();
+    for entry in entries.iter() {
+        if let Some(season) = entry.season {
+            trajectories
+                .entry(entry.player_id)
+                .or_insert_with(|| (entry.player_name.clone(), Vec::new()))
+                .1
+                .push((season, entry.rank));
+        }
+    }
+
+    trajectories
+        .into_values()
+        .filter(|(_, ranks)| ranks.iter().any(|(_, rank)| *rank <= top_n))
+        .map(|(name, mut ranks)| {
+            ranks.sort_unstable();
+            (name, ranks)
+        })
+        .collect()
+}
+
+pub fn print_leaderboard(metric: FootballMetric, entries: &[LeaderboardEntry]) {
+    println!(
+        "{:>6} | {:>4} | {:<25} | {:>7} | {}",
+        "season", "rank", "player", "minutes", metric
+    );
+    for entry in entries.iter() {
+        let season = entry
+            .season
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "all".to_string());
+        println!(
+            "{:>6} | {:>4} | {:<25} | {:>7} | {:.2}",
+            season, entry.rank, entry.player_name, entry.minutes, entry.value
+        );
+    }
+}
+
+pub fn write_leaderboard_csv(
+    metric: FootballMetric,
+    entries: &[LeaderboardEntry],
+    file_path: &Path,
+) -> Result<(), AppError> {
+    let mut df = DataFrame::new(vec![
+        Series::new(
+            "season",
+            entries
+                .iter()
+                .map(|e| e.season)
+                .collect::<Vec<Option<i32>>>(),
+        ),
+        Series::new(
+            "rank",
+            entries.iter().map(|e| e.rank as u32).collect::<Vec<u32>>(),
+        ),
+        Series::new(
+            "player_id",
+            entries.iter().map(|e| e.player_id).collect::<Vec<i32>>(),
+        ),
+        Series::new(
+            "player_name",
+            entries
+                .iter()
+                .map(|e| e.player_name.clone())
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "minutes",
+            entries.iter().map(|e| e.minutes).collect::<Vec<i32>>(),
+        ),
+        Series::new(
+            metric.name(),
+            entries.iter().map(|e| e.value).collect::<Vec<f32>>(),
+        ),
+    ])?;
+
+    let mut file = std::fs::File::create(file_path)?;
+    CsvWriter::new(&mut file).has_header(true).finish(&mut df)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A data frame of statistics split by team, i.e. with a row per player, season and team.
+    fn team_split_df(rows: &[(i32, i32, &str, i32, i32)]) -> DataFrame {
+        let mut columns = vec![
+            Series::new("season", rows.iter().map(|r| r.0).collect::<Vec<i32>>()),
+            Series::new("player_id", rows.iter().map(|r| r.1).collect::<Vec<i32>>()),
+            Series::new(
+                "player_name",
+                rows.iter()
+                    .map(|r| r.2.to_string())
+                    .collect::<Vec<String>>(),
+            ),
+            Series::new(
+                "age",
+                rows.iter().map(|_| Some(25)).collect::<Vec<Option<i32>>>(),
+            ),
+        ];
+        for name in FootballMetric::TOTAL_COLUMNS.iter() {
+            let values: Vec<i32> = rows
+                .iter()
+                .map(|r| match *name {
+                    "minutes" => r.3,
+                    "goals" => r.4,
+                    _ => 0,
+                })
+                .collect();
+            columns.push(Series::new(name, values));
+        }
+        DataFrame::new(columns).unwrap()
+    }
+
+    #[test]
+    fn ranks_team_split_rows_once_per_player_and_season() {
+        // player 1 was transferred within the season 2021
+        let df = team_split_df(&[
+            (2021, 1, "Transferred", 900, 5),
+            (2021, 1, "Transferred", 900, 5),
+            (2021, 2, "Stayed", 1800, 8),
+            (2022, 1, "Transferred", 1800, 4),
+        ]);
+        let entries =
+            rank_players(&df, FootballMetric::GoalsPer90, 0, RankingScope::Season).unwrap();
+        let ranking: Vec<(Option<i32>, usize, i32, i32)> = entries
+            .iter()
+            .map(|e| (e.season, e.rank, e.player_id, e.minutes))
+            .collect();
+        assert_eq!(
+            ranking,
+            vec![
+                (Some(2021), 1, 1, 1800),
+                (Some(2021), 2, 2, 1800),
+                (Some(2022), 1, 1, 1800)
+            ]
+        );
+        assert!((entries[0].value - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ranks_ties_by_player_id() {
+        let df = team_split_df(&[
+            (2021, 7, "Seventh", 900, 5),
+            (2021, 3, "Third", 900, 5),
+            (2021, 5, "Fifth", 900, 9),
+        ]);
+        let entries =
+            rank_players(&df, FootballMetric::GoalsPer90, 0, RankingScope::Season).unwrap();
+        let ranking: Vec<(usize, i32)> = entries.iter().map(|e| (e.rank, e.player_id)).collect();
+        assert_eq!(ranking, vec![(1, 5), (2, 3), (3, 7)]);
+    }
+}
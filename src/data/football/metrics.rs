@@ -0,0 +1,197 @@
+use crate::data::football::models::PlayerStats;
+use polars::prelude::*;
+
+/// The raw totals of a statistics entry of a player from which the metrics are derived.
+#[derive(Debug, Default, Clone)]
+pub struct PlayerSeasonTotals {
+    pub minutes: i32,
+    pub appearances: i32,
+    pub goals: i32,
+    pub goals_conceded: i32,
+    pub passes: i32,
+    pub passes_accurate: i32,
+    pub duels: i32,
+    pub duels_won: i32,
+    pub cards_weighted: i32,
+}
+
+impl From<&PlayerStats> for PlayerSeasonTotals {
+    fn from(stats: &PlayerStats) -> Self {
+        let games = stats.games.as_ref();
+        let goals = stats.goals.as_ref();
+        let passes = stats.passes.as_ref();
+        let duels = stats.duels.as_ref();
+
+        let passes_total = passes.and_then(|p| p.total).unwrap_or(0);
+        // the accuracy is provided as percentage of the passes
+        let passes_accurate = passes
+            .and_then(|p| p.accuracy)
+            .map(|accuracy| (passes_total as f32 * accuracy as f32 / 100.0).round() as i32)
+            .unwrap_or(0);
+
+        let cards_weighted = stats
+            .cards
+            .as_ref()
+            .map(|cards| {
+                cards.red.unwrap_or(0) * 3
+                    + cards.yellowred.unwrap_or(0) * 2
+                    + cards.yellow.unwrap_or(0)
+            })
+            .unwrap_or(0);
+
+        Self {
+            minutes: games.and_then(|g| g.minutes).unwrap_or(0),
+            appearances: games.and_then(|g| g.appearences).unwrap_or(0),
+            goals: goals.and_then(|g| g.total).unwrap_or(0),
+            goals_conceded: goals.and_then(|g| g.conceded).unwrap_or(0),
+            passes: passes_total,
+            passes_accurate,
+            duels: duels.and_then(|d| d.total).unwrap_or(0),
+            duels_won: duels.and_then(|d| d.won).unwrap_or(0),
+            cards_weighted,
+        }
+    }
+}
+
+/// The registry of the metrics which can be derived from the player statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum FootballMetric {
+    #[clap(name = "goals_per_90")]
+    GoalsPer90,
+    #[clap(name = "passes_per_90")]
+    PassesPer90,
+    #[clap(name = "duel_win_rate")]
+    DuelWinRate,
+    #[clap(name = "pass_accuracy")]
+    PassAccuracy,
+    #[clap(name = "minutes_per_goal")]
+    MinutesPerGoal,
+    #[clap(name = "appearances")]
+    Appearances,
+    #[clap(name = "discipline_per_90")]
+    DisciplinePer90,
+    #[clap(name = "goals_conceded_per_90")]
+    GoalsConcededPer90,
+}
+
+fn per_90(column: &str) -> Expr {
+    ratio(
+        lit(90.0f32) * col(column).cast(DataType::Float32),
+        "minutes",
+    )
+}
+
+fn percentage(column: &str, total_column: &str) -> Expr {
+    ratio(
+        lit(100.0f32) * col(column).cast(DataType::Float32),
+        total_column,
+    )
+}
+
+/// Null if the denominator is zero, e.g. for players without any minutes played.
+fn ratio(numerator: Expr, denominator: &str) -> Expr {
+    when(col(denominator).eq(lit(0)))
+        .then(lit(Null {}).cast(DataType::Float32))
+        .otherwise(numerator / col(denominator).cast(DataType::Float32))
+}
+
+impl FootballMetric {
+    /// The raw total columns which are summed up when aggregating statistics entries.
+    pub const TOTAL_COLUMNS: [&'static str; 9] = [
+        "minutes",
+        "appearances",
+        "goals",
+        "goals_conceded",
+        "passes",
+        "passes_accurate",
+        "duels",
+        "duels_won",
+        "cards_weighted",
+    ];
+
+    pub const ALL: [FootballMetric; 8] = [
+        FootballMetric::GoalsPer90,
+        FootballMetric::PassesPer90,
+        FootballMetric::DuelWinRate,
+        FootballMetric::PassAccuracy,
+        FootballMetric::MinutesPerGoal,
+        FootballMetric::Appearances,
+        FootballMetric::DisciplinePer90,
+        FootballMetric::GoalsConcededPer90,
+    ];
+
+    /// The name of the metric which is also used as column name in the data frames.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FootballMetric::GoalsPer90 => "goals_per_90",
+            FootballMetric::PassesPer90 => "passes_per_90",
+            FootballMetric::DuelWinRate => "duel_win_rate",
+            FootballMetric::PassAccuracy => "pass_accuracy",
+            FootballMetric::MinutesPerGoal => "minutes_per_goal",
+            FootballMetric::Appearances => "appearances",
+            FootballMetric::DisciplinePer90 => "discipline_per_90",
+            FootballMetric::GoalsConcededPer90 => "goals_conceded_per_90",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            FootballMetric::GoalsPer90 => "goals / 90 min",
+            FootballMetric::PassesPer90 => "passes / 90 min",
+            FootballMetric::DuelWinRate => "%",
+            FootballMetric::PassAccuracy => "%",
+            FootballMetric::MinutesPerGoal => "min / goal",
+            FootballMetric::Appearances => "games",
+            FootballMetric::DisciplinePer90 => "weighted cards / 90 min",
+            FootballMetric::GoalsConcededPer90 => "goals / 90 min",
+        }
+    }
+
+    pub fn formula(&self) -> &'static str {
+        match self {
+            FootballMetric::GoalsPer90 => "90 * goals / minutes",
+            FootballMetric::PassesPer90 => "90 * passes / minutes",
+            FootballMetric::DuelWinRate => "100 * duels won / duels",
+            FootballMetric::PassAccuracy => "100 * accurate passes / passes",
+            FootballMetric::MinutesPerGoal => "minutes / goals",
+            FootballMetric::Appearances => "appearances",
+            FootballMetric::DisciplinePer90 => {
+                "90 * (yellow + 2 * yellow-red + 3 * red cards) / minutes"
+            }
+            FootballMetric::GoalsConcededPer90 => "90 * goals conceded / minutes",
+        }
+    }
+
+    /// Whether a higher value of the metric ranks a player higher, e.g. not for the minutes per goal.
+    pub fn higher_is_better(&self) -> bool {
+        !matches!(
+            self,
+            FootballMetric::MinutesPerGoal
+                | FootballMetric::DisciplinePer90
+                | FootballMetric::GoalsConcededPer90
+        )
+    }
+
+    /// The expression to compute the metric from the raw total columns of a data frame.
+    pub fn expr(&self) -> Expr {
+        let expr = match self {
+            FootballMetric::GoalsPer90 => per_90("goals"),
+            FootballMetric::PassesPer90 => per_90("passes"),
+            FootballMetric::DuelWinRate => percentage("duels_won", "duels"),
+            FootballMetric::PassAccuracy => percentage("passes_accurate", "passes"),
+            FootballMetric::MinutesPerGoal => {
+                ratio(col("minutes").cast(DataType::Float32), "goals")
+            }
+            FootballMetric::Appearances => col("appearances").cast(DataType::Float32),
+            FootballMetric::DisciplinePer90 => per_90("cards_weighted"),
+            FootballMetric::GoalsConcededPer90 => per_90("goals_conceded"),
+        };
+        expr.alias(self.name())
+    }
+}
+
+impl std::fmt::Display for FootballMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.name(), self.unit())
+    }
+}
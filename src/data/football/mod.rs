@@ -1,7 +1,22 @@
 mod conversions;
+#[allow(clippy::module_inception)]
 mod football;
+mod leaderboard;
+mod metrics;
 pub mod models;
+mod profiles;
+mod teams;
 
-pub use conversions::{column_vec, convert_data_frame, filter_players, join_players};
-pub use football::FootballLeague;
+pub use conversions::{
+    column_values, convert_data_frame, filter_players, pivot_column, pivot_players, player_names,
+    CareerAxis, PlayerFilter, StatisticsGrouping,
+};
+pub use football::{FootballLeague, API_SPORTS_PLAYERS_URL};
+pub use leaderboard::{
+    print_leaderboard, rank_players, rank_trajectories, top_entries, write_leaderboard_csv,
+    RankingScope,
+};
+pub use metrics::FootballMetric;
 pub use models::FootballSeasonResults;
+pub use profiles::player_profiles;
+pub use teams::{aggregate_teams, team_curves, TeamMetric};
@@ -1,15 +1,50 @@
+use chrono::{Datelike, NaiveDate};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 pub struct FootballPlayer {
     #[serde(default)]
     pub id: i32,
+    pub name: Option<String>,
     pub firstname: String,
     pub lastname: String,
+    pub birth: Option<PlayerBirth>,
+}
+
+impl FootballPlayer {
+    /// The short name as used by api-sports, e.g. 'L. Messi'.
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{} {}", self.firstname, self.lastname))
+    }
+
+    pub fn birth_date(&self) -> Option<NaiveDate> {
+        self.birth
+            .as_ref()
+            .and_then(|b| b.date.as_ref())
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    }
+
+    /// Age in full years at the start of the season, i.e. on the 1st of July of the season year.
+    pub fn age_in_season(&self, season: i32) -> Option<i32> {
+        let birth_date = self.birth_date()?;
+        let season_start = NaiveDate::from_ymd_opt(season, 7, 1)?;
+        let had_birthday =
+            (season_start.month(), season_start.day()) >= (birth_date.month(), birth_date.day());
+        Some(season - birth_date.year() - if had_birthday { 0 } else { 1 })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlayerBirth {
+    pub date: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct PlayerStats {
+    pub team: Option<PlayerStatsTeam>,
+    pub league: Option<PlayerStatsLeague>,
     pub games: Option<PlayerStatsGames>,
     pub goals: Option<PlayerStatsGoals>,
     pub cards: Option<PlayerStatsCards>,
@@ -17,6 +52,17 @@ pub struct PlayerStats {
     pub passes: Option<PlayerStatsPasses>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PlayerStatsTeam {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlayerStatsLeague {
+    pub name: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PlayerStatsGames {
     pub minutes: Option<i32>,
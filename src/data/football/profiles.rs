@@ -0,0 +1,95 @@
+use crate::data::football::conversions::{aggregate_careers, aggregate_player_seasons};
+use crate::data::football::metrics::FootballMetric;
+use crate::errors::AppError;
+use log::warn;
+use polars::prelude::*;
+use std::result::Result;
+
+/// The metrics of a player normalized to percentiles (0-100) of all players of the league,
+/// such that a higher percentile is always better, e.g. for the discipline fewer cards are better.
+#[derive(Debug, Clone)]
+pub struct PlayerProfile {
+    pub player_id: i32,
+    pub player_name: String,
+    /// The percentile per metric, none if the metric is undefined for the player, e.g. the minutes per goal without goals
+    pub percentiles: Vec<(FootballMetric, Option<f32>)>,
+}
+
+/// The percentage of the values which are not better than the value.
+fn percentile_of(value: f32, values: &[f32], higher_is_better: bool) -> f32 {
+    let not_better = values
+        .iter()
+        .filter(|&&v| {
+            if higher_is_better {
+                v <= value
+            } else {
+                v >= value
+            }
+        })
+        .count();
+    100.0 * not_better as f32 / values.len() as f32
+}
+
+/// Builds the profiles of the players for the season, or over their careers if no season is given.
+/// Statistics split by team are aggregated per player and season first, see `aggregate_player_seasons`.
+/// The reference group for the percentiles are all players with at least `min_minutes` played,
+/// the requested players outside of the reference group are skipped (and logged).
+pub fn player_profiles(
+    df: &DataFrame,
+    player_ids: &[i32],
+    metrics: &[FootballMetric],
+    season: Option<i32>,
+    min_minutes: i32,
+) -> Result<Vec<PlayerProfile>, AppError> {
+    let lf = match season {
+        Some(season) => aggregate_player_seasons(df).filter(col("season").eq(lit(season))),
+        None => aggregate_careers(df),
+    };
+    let reference = lf
+        .filter(col("minutes").gt_eq(lit(min_minutes)))
+        .collect()?;
+
+    let ids: Vec<Option<i32>> = reference.column("player_id")?.i32()?.into_iter().collect();
+    let names = reference.column("player_name")?.utf8()?;
+
+    let mut profiles: Vec<PlayerProfile> = player_ids
+        .iter()
+        .flat_map(|player_id| {
+            let idx = ids.iter().position(|id| *id == Some(*player_id));
+            if idx.is_none() {
+                warn!(
+                    "Skipping the player {} without statistics of at least {} minutes",
+                    player_id, min_minutes
+                );
+            }
+            let idx = idx?;
+            Some(PlayerProfile {
+                player_id: *player_id,
+                player_name: names.get(idx).unwrap_or_default().to_string(),
+                percentiles: Vec::with_capacity(metrics.len()),
+            })
+        })
+        .collect();
+
+    for metric in metrics.iter() {
+        let values: Vec<Option<f32>> = reference
+            .column(metric.name())?
+            .cast(&DataType::Float32)?
+            .f32()?
+            .into_iter()
+            .collect();
+        let defined_values: Vec<f32> = values.iter().flatten().cloned().collect();
+
+        for profile in profiles.iter_mut() {
+            let value = ids
+                .iter()
+                .position(|id| *id == Some(profile.player_id))
+                .and_then(|idx| values[idx]);
+            let percentile =
+                value.map(|v| percentile_of(v, &defined_values, metric.higher_is_better()));
+            profile.percentiles.push((*metric, percentile));
+        }
+    }
+
+    Ok(profiles)
+}
@@ -0,0 +1,135 @@
+use crate::errors::AppError;
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::result::Result;
+
+/// The metrics of a team within a season.
+/// NOTE: the team totals are aggregated over the loaded players only, e.g. the top scorers of a league.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TeamMetric {
+    #[clap(name = "team_goals")]
+    TeamGoals,
+    #[clap(name = "top_scorer_share")]
+    TopScorerShare,
+    #[clap(name = "squad_discipline_per_90")]
+    SquadDisciplinePer90,
+    #[clap(name = "team_goals_change")]
+    TeamGoalsChange,
+    #[clap(name = "squad_discipline_change")]
+    SquadDisciplineChange,
+}
+
+impl TeamMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TeamMetric::TeamGoals => "team_goals",
+            TeamMetric::TopScorerShare => "top_scorer_share",
+            TeamMetric::SquadDisciplinePer90 => "squad_discipline_per_90",
+            TeamMetric::TeamGoalsChange => "team_goals_change",
+            TeamMetric::SquadDisciplineChange => "squad_discipline_change",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            TeamMetric::TeamGoals => "goals",
+            TeamMetric::TopScorerShare => "%",
+            TeamMetric::SquadDisciplinePer90 => "weighted cards / 90 min",
+            TeamMetric::TeamGoalsChange => "goals vs. previous season",
+            TeamMetric::SquadDisciplineChange => "weighted cards / 90 min vs. previous season",
+        }
+    }
+}
+
+impl std::fmt::Display for TeamMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.name(), self.unit())
+    }
+}
+
+/// Aggregates a data frame of statistics split by team (see `StatisticsGrouping::Team`)
+/// into one row per team and season, containing a column for each team metric.
+pub fn aggregate_teams(df: &DataFrame) -> Result<DataFrame, AppError> {
+    let teams = df
+        .clone()
+        .lazy()
+        .filter(col("team_id").is_not_null())
+        .groupby([col("season"), col("team_id")])
+        .agg([
+            col("team").first(),
+            col("league").first(),
+            col("player_id").n_unique().alias("squad_size"),
+            col("goals").sum().alias("team_goals"),
+            col("goals").max().alias("top_scorer_goals"),
+            col("minutes").sum(),
+            col("cards_weighted").sum(),
+        ])
+        .with_columns(vec![
+            when(col("team_goals").eq(lit(0)))
+                .then(lit(Null {}).cast(DataType::Float32))
+                .otherwise(
+                    lit(100.0f32) * col("top_scorer_goals").cast(DataType::Float32)
+                        / col("team_goals").cast(DataType::Float32),
+                )
+                .alias(TeamMetric::TopScorerShare.name()),
+            when(col("minutes").eq(lit(0)))
+                .then(lit(Null {}).cast(DataType::Float32))
+                .otherwise(
+                    lit(90.0f32) * col("cards_weighted").cast(DataType::Float32)
+                        / col("minutes").cast(DataType::Float32),
+                )
+                .alias(TeamMetric::SquadDisciplinePer90.name()),
+        ])
+        // the trends require the seasons of each team to be in order
+        .sort_by_exprs(
+            vec![col("team_id"), col("season")],
+            vec![false, false],
+            false,
+        )
+        .with_columns(vec![
+            (col("team_goals") - col("team_goals").shift(1).over([col("team_id")]))
+                .alias(TeamMetric::TeamGoalsChange.name()),
+            (col(TeamMetric::SquadDisciplinePer90.name())
+                - col(TeamMetric::SquadDisciplinePer90.name())
+                    .shift(1)
+                    .over([col("team_id")]))
+            .alias(TeamMetric::SquadDisciplineChange.name()),
+        ])
+        .collect()?;
+
+    Ok(teams)
+}
+
+/// The name of a team with its (season, value) points.
+pub type TeamCurve = (String, Vec<(f32, f32)>);
+
+/// The curves (season, value) of the metric per team, optionally restricted to the given team ids.
+pub fn team_curves(
+    teams_df: &DataFrame,
+    metric: TeamMetric,
+    team_ids: &[i32],
+) -> Result<Vec<TeamCurve>, AppError> {
+    let seasons = teams_df.column("season")?.i32()?;
+    let ids = teams_df.column("team_id")?.i32()?;
+    let names = teams_df.column("team")?.utf8()?;
+    let values = teams_df.column(metric.name())?.cast(&DataType::Float32)?;
+    let values = values.f32()?;
+
+    let mut curves: BTreeMap<i32, (String, Vec<(f32, f32)>)> = BTreeMap::new();
+    for idx in 0..teams_df.height() {
+        let (season, team_id, value) = match (seasons.get(idx), ids.get(idx), values.get(idx)) {
+            (Some(season), Some(team_id), Some(value)) => (season, team_id, value),
+            _ => continue,
+        };
+        if !team_ids.is_empty() && !team_ids.contains(&team_id) {
+            continue;
+        }
+        curves
+            .entry(team_id)
+            .or_insert_with(|| (names.get(idx).unwrap_or_default().to_string(), Vec::new()))
+            .1
+            .push((season as f32, value));
+    }
+
+    Ok(curves.into_values().collect())
+}
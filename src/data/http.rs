@@ -0,0 +1,393 @@
+use crate::errors::AppError;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The query parameters which are masked in the logs.
+const SECRET_PARAMS: [&str; 1] = ["key"];
+/// The headers announcing when the rate limit is reset, in seconds or as unix timestamp.
+const RATE_LIMIT_RESET_HEADERS: [&str; 2] = ["x-ratelimit-reset", "x-ratelimit-requests-reset"];
+/// The headers containing the number of remaining requests within the rate limit.
+const RATE_LIMIT_REMAINING_HEADERS: [&str; 2] =
+    ["x-ratelimit-remaining", "x-ratelimit-requests-remaining"];
+/// The maximal length of a response body within an error message.
+const MAX_ERROR_BODY_LENGTH: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub timeout: Duration,
+    /// The number of retries after the first attempt
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled for each further retry
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Requests are not retried if the server asks to wait longer, e.g. if the daily quota is exhausted
+    pub max_wait: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_retries: 4,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_wait: Duration::from_secs(600),
+        }
+    }
+}
+
+/// The HTTP client of the data providers, validating the status and retrying failed requests.
+/// Clones share the connection pool.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    config: HttpConfig,
+}
+
+impl HttpClient {
+    pub fn new(config: HttpConfig) -> Result<Self, AppError> {
+        let client = Client::builder().timeout(config.timeout).build()?;
+        Ok(Self { client, config })
+    }
+
+    /// Requests the JSON content of the url, see `get`.
+    pub fn get_json(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, String)],
+    ) -> Result<Value, AppError> {
+        Ok(self.get(url, query, headers)?.json::<Value>()?)
+    }
+
+    /// Requests the content of the url as text, e.g. a CSV file, see `get`.
+    pub fn get_text(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, String)],
+    ) -> Result<String, AppError> {
+        Ok(self.get(url, query, headers)?.text()?)
+    }
+
+    /// Requests the url and returns the successful response.
+    /// Server errors, rate limits (429) and network failures are retried with exponential backoff,
+    /// respecting the `Retry-After` and rate-limit reset headers; other client errors fail immediately.
+    fn get(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, String)],
+    ) -> Result<Response, AppError> {
+        let mut attempt = 0;
+        loop {
+            info!("GET {}{}", url, loggable_query(query));
+            let started = Instant::now();
+
+            let wait = match self.send(url, query, headers) {
+                Ok(response) => {
+                    let status = response.status();
+                    debug!("{} from {} after {:?}", status, url, started.elapsed());
+                    log_rate_limit(response.headers());
+                    if status.is_success() {
+                        return Ok(response);
+                    }
+
+                    let wait = server_wait(response.headers());
+                    let body = response.text().unwrap_or_default();
+                    let error = format!("{} from {}: {}", status, url, truncate(&body));
+                    if !is_retryable(status) || attempt >= self.config.max_retries {
+                        return Err(AppError::Http(error));
+                    }
+                    warn!("Request failed with {}", error);
+                    wait
+                }
+                Err(e) => {
+                    let retryable = e.is_timeout() || e.is_connect() || e.is_request();
+                    if !retryable || attempt >= self.config.max_retries {
+                        return Err(e.into());
+                    }
+                    warn!("Request to {} failed: {}", url, e);
+                    None
+                }
+            };
+
+            let wait = wait.unwrap_or_else(|| self.backoff(attempt));
+            if wait > self.config.max_wait {
+                return Err(AppError::Http(format!(
+                    "{} asks to wait {:?} before retrying",
+                    url, wait
+                )));
+            }
+            attempt += 1;
+            info!(
+                "Retrying in {:?} (retry {} of {})",
+                wait, attempt, self.config.max_retries
+            );
+            thread::sleep(wait);
+        }
+    }
+
+    fn send(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+        headers: &[(&str, String)],
+    ) -> Result<Response, reqwest::Error> {
+        let mut request = self.client.get(url).query(query);
+        for (name, value) in headers.iter() {
+            request = request.header(*name, value);
+        }
+        request.send()
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self.config.initial_backoff * 2u32.saturating_pow(attempt);
+        backoff.min(self.config.max_backoff)
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The time the server asks to wait before retrying, by the `Retry-After` (seconds or HTTP date)
+/// or the rate-limit reset header (seconds or unix timestamp).
+fn server_wait(headers: &HeaderMap) -> Option<Duration> {
+    let header_value = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(retry_after) = header_value(RETRY_AFTER.as_str()) {
+        if let Ok(seconds) = retry_after.trim().parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        if let Ok(date) = DateTime::parse_from_rfc2822(retry_after) {
+            return (date.with_timezone(&Utc) - Utc::now()).to_std().ok();
+        }
+    }
+
+    RATE_LIMIT_RESET_HEADERS
+        .iter()
+        .flat_map(|name| header_value(name))
+        .flat_map(|v| v.trim().parse::<i64>().ok())
+        .map(|reset| {
+            // larger values are unix timestamps rather than seconds to wait
+            if reset > 1_000_000_000 {
+                (reset - Utc::now().timestamp()).max(0) as u64
+            } else {
+                reset.max(0) as u64
+            }
+        })
+        .map(Duration::from_secs)
+        .next()
+}
+
+fn log_rate_limit(headers: &HeaderMap) {
+    for name in RATE_LIMIT_REMAINING_HEADERS.iter() {
+        if let Some(remaining) = headers.get(*name).and_then(|v| v.to_str().ok()) {
+            debug!("{} requests remaining within the rate limit", remaining);
+            if remaining.trim() == "0" {
+                warn!("The rate limit is exhausted");
+            }
+        }
+    }
+}
+
+fn loggable_query(query: &[(&str, String)]) -> String {
+    let params: Vec<String> = query
+        .iter()
+        .map(|(name, value)| {
+            if SECRET_PARAMS.contains(name) {
+                format!("{}=***", name)
+            } else {
+                format!("{}={}", name, value)
+            }
+        })
+        .collect();
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", params.join("&"))
+    }
+}
+
+fn truncate(body: &str) -> &str {
+    match body.char_indices().nth(MAX_ERROR_BODY_LENGTH) {
+        Some((idx, _)) => &body[..idx],
+        None => body,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn config() -> HttpConfig {
+        HttpConfig {
+            timeout: Duration::from_secs(5),
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(100),
+            max_wait: Duration::from_secs(5),
+        }
+    }
+
+    fn response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {}\r\n", status);
+        for header in headers.iter() {
+            response.push_str(&format!("{}\r\n", header));
+        }
+        response.push_str(&format!(
+            "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        response
+    }
+
+    /// Serves the responses in order, one per connection, on a local port.
+    /// Returns the url along with the number of requests served so far.
+    fn serve(responses: Vec<String>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/data", listener.local_addr().unwrap());
+        let served = Arc::new(AtomicUsize::new(0));
+        let counter = served.clone();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, served)
+    }
+
+    fn get_json(url: &str) -> Result<Value, AppError> {
+        HttpClient::new(config()).unwrap().get_json(url, &[], &[])
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (url, served) = serve(vec![
+            response("500 Internal Server Error", &[], "{}"),
+            response("200 OK", &[], r#"{"temperature": 21.5}"#),
+        ]);
+        let json = get_json(&url).unwrap();
+        assert_eq!(json["temperature"], 21.5);
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn fails_immediately_on_client_errors() {
+        let (url, served) = serve(vec![
+            response("404 Not Found", &[], r#"{"message": "unknown zip"}"#),
+            response("200 OK", &[], "{}"),
+        ]);
+        let result = get_json(&url);
+        assert!(matches!(result, Err(AppError::Http(message)) if message.contains("404")));
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn honours_retry_after() {
+        let (url, served) = serve(vec![
+            response("429 Too Many Requests", &["Retry-After: 1"], "{}"),
+            response("200 OK", &[], "{}"),
+        ]);
+        let started = Instant::now();
+        assert!(get_json(&url).is_ok());
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(served.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn aborts_if_the_wait_exceeds_max_wait() {
+        let (url, served) = serve(vec![
+            response("429 Too Many Requests", &["Retry-After: 3600"], "{}"),
+            response("200 OK", &[], "{}"),
+        ]);
+        let result = get_json(&url);
+        assert!(matches!(result, Err(AppError::Http(message)) if message.contains("asks to wait")));
+        assert_eq!(served.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn never_returns_error_bodies_as_json() {
+        let body = r#"{"errors": {"token": "invalid"}}"#;
+        let (url, served) = serve(vec![
+            response("503 Service Unavailable", &[], body),
+            response("503 Service Unavailable", &[], body),
+            response("503 Service Unavailable", &[], body),
+        ]);
+        let result = get_json(&url);
+        assert!(matches!(result, Err(AppError::Http(message)) if message.contains("503")));
+        assert_eq!(served.load(Ordering::SeqCst), 3);
+    }
+
+    fn headers(name: &'static str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    /// Whether the wait is close to the expected seconds, allowing for the time passing during the test.
+    fn is_about(wait: Option<Duration>, seconds: u64) -> bool {
+        let seconds = Duration::from_secs(seconds);
+        matches!(wait, Some(wait) if wait <= seconds && wait + Duration::from_secs(2) >= seconds)
+    }
+
+    #[test]
+    fn server_wait_of_retry_after_seconds() {
+        let wait = server_wait(&headers("retry-after", "120"));
+        assert_eq!(wait, Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn server_wait_of_retry_after_date() {
+        let date = Utc::now() + chrono::Duration::seconds(60);
+        let date = date.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        assert!(is_about(server_wait(&headers("retry-after", &date)), 60));
+    }
+
+    #[test]
+    fn server_wait_of_rate_limit_reset() {
+        let wait = server_wait(&headers("x-ratelimit-requests-reset", "15"));
+        assert_eq!(wait, Some(Duration::from_secs(15)));
+
+        let reset = (Utc::now().timestamp() + 30).to_string();
+        assert!(is_about(
+            server_wait(&headers("x-ratelimit-reset", &reset)),
+            30
+        ));
+    }
+
+    #[test]
+    fn server_wait_without_headers() {
+        assert_eq!(server_wait(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn loggable_query_masks_the_key() {
+        let query = [("key", "secret".to_string()), ("zip", "8000".to_string())];
+        assert_eq!(loggable_query(&query), "?key=***&zip=8000");
+        assert_eq!(loggable_query(&[]), "");
+    }
+}
@@ -1,8 +1,16 @@
+pub mod covid;
 mod data_provider;
+pub mod dataset;
 pub mod football;
+pub mod http;
 pub mod models;
+pub mod validation;
 pub mod weather;
 
-pub use data_provider::{DataProvider, FootballProvider, WeatherProvider};
-pub use football::FootballLeague;
+pub use data_provider::{
+    Compression, CovidProvider, DataLock, DataProvider, DatasetProvider, FootballProvider,
+    WeatherProvider, DEFAULT_DATA_ROOT,
+};
+pub use football::{FootballLeague, API_SPORTS_PLAYERS_URL};
+pub use http::{HttpClient, HttpConfig};
 pub use weather::WeatherLocation;
@@ -1,12 +1,36 @@
 use super::weather::models::ForecastTemperaturePoint;
-use chrono::Date;
-use chrono::Utc;
+use chrono::Datelike;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use std::collections::{btree_map, BTreeMap, BTreeSet};
+use std::ops::{Add, RangeBounds, Sub};
 
-pub type TimeSeries = Vec<TimeSeriesPoint>;
+/// The index of a time series, i.e. the date of daily values or the date-time of sub-daily values.
+pub trait TimeIndex:
+    Copy
+    + Ord
+    + std::fmt::Debug
+    + std::fmt::Display
+    + Sub<Output = Duration>
+    + Add<Duration, Output = Self>
+{
+}
 
-#[derive(Debug, Clone)]
-pub struct TimeSeriesPoint {
-    pub date: Date<Utc>,
+impl<T> TimeIndex for T where
+    T: Copy
+        + Ord
+        + std::fmt::Debug
+        + std::fmt::Display
+        + Sub<Output = Duration>
+        + Add<Duration, Output = T>
+{
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeriesPoint<T = NaiveDate> {
+    /// The date, resp. date-time of sub-daily series
+    pub date: T,
     pub value: f32,
 }
 
@@ -18,3 +42,642 @@ impl From<&ForecastTemperaturePoint> for TimeSeriesPoint {
         }
     }
 }
+
+/// The calendar periods to which a time series can be resampled.
+#[derive(Debug, Clone, Copy)]
+pub enum Frequency {
+    /// Weeks starting on Mondays
+    Weekly,
+    Monthly,
+}
+
+impl Frequency {
+    /// The first date of the period containing the date.
+    fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Frequency::Weekly => {
+                date - Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            Frequency::Monthly => date.with_day(1).unwrap(),
+        }
+    }
+}
+
+/// The aggregation of several values, e.g. within a resampling period or rolling window.
+#[derive(Debug, Clone, Copy)]
+pub enum Aggregation {
+    Mean,
+    Sum,
+    Min,
+    Max,
+    Last,
+}
+
+impl Aggregation {
+    fn apply(&self, values: &[f32]) -> Option<f32> {
+        if values.is_empty() {
+            return None;
+        }
+        let aggregated = match self {
+            Aggregation::Mean => values.iter().sum::<f32>() / values.len() as f32,
+            Aggregation::Sum => values.iter().sum(),
+            Aggregation::Min => values.iter().cloned().fold(f32::INFINITY, f32::min),
+            Aggregation::Max => values.iter().cloned().fold(f32::NEG_INFINITY, f32::max),
+            Aggregation::Last => values[values.len() - 1],
+        };
+        Some(aggregated)
+    }
+}
+
+/// How missing values, i.e. missing calendar days or `NaN` values, are filled.
+#[derive(Debug, Clone, Copy)]
+pub enum FillMethod {
+    /// Repeat the last known value
+    Forward,
+    /// Interpolate linearly between the surrounding known values
+    Linear,
+}
+
+/// A series of values indexed by date, resp. date-time for sub-daily series (see `HourlySeries`).
+/// Invariant: the points are sorted by date and each date occurs at most once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries<T = NaiveDate> {
+    points: Vec<TimeSeriesPoint<T>>,
+}
+
+/// A series of hourly values indexed by their (local) date-time.
+pub type HourlySeries = TimeSeries<NaiveDateTime>;
+
+impl<T> Default for TimeSeries<T> {
+    fn default() -> Self {
+        Self { points: Vec::new() }
+    }
+}
+
+impl<T: TimeIndex> TimeSeries<T> {
+    /// Creates the time series from points in arbitrary order, for duplicate dates the last point is kept.
+    pub fn new(mut points: Vec<TimeSeriesPoint<T>>) -> Self {
+        points.reverse();
+        // the sort is stable, i.e. the (originally) last point of a date comes first
+        points.sort_by_key(|tsp| tsp.date);
+        points.dedup_by_key(|tsp| tsp.date);
+        Self { points }
+    }
+
+    pub fn points(&self) -> &[TimeSeriesPoint<T>] {
+        &self.points
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, TimeSeriesPoint<T>> {
+        self.points.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn first(&self) -> Option<&TimeSeriesPoint<T>> {
+        self.points.first()
+    }
+
+    pub fn last(&self) -> Option<&TimeSeriesPoint<T>> {
+        self.points.last()
+    }
+
+    pub fn get(&self, date: &T) -> Option<f32> {
+        self.points
+            .binary_search_by_key(date, |tsp| tsp.date)
+            .ok()
+            .map(|idx| self.points[idx].value)
+    }
+
+    pub fn map(&self, f: impl Fn(f32) -> f32) -> TimeSeries<T> {
+        let points = self
+            .iter()
+            .map(|tsp| TimeSeriesPoint {
+                date: tsp.date,
+                value: f(tsp.value),
+            })
+            .collect();
+        Self { points }
+    }
+
+    /// The values of both series at their common dates.
+    pub fn align(&self, other: &TimeSeries<T>) -> Vec<(T, f32, f32)> {
+        let mut aligned = Vec::with_capacity(self.len().min(other.len()));
+        let (mut i, mut j) = (0, 0);
+        while i < self.len() && j < other.len() {
+            let (p, q) = (&self.points[i], &other.points[j]);
+            match p.date.cmp(&q.date) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    aligned.push((p.date, p.value, q.value));
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        aligned
+    }
+
+    /// Combines the values of both series at their common dates, dropping undefined results.
+    pub fn zip_with(
+        &self,
+        other: &TimeSeries<T>,
+        f: impl Fn(f32, f32) -> Option<f32>,
+    ) -> TimeSeries<T> {
+        let points = self
+            .align(other)
+            .into_iter()
+            .flat_map(|(date, v, w)| f(v, w).map(|value| TimeSeriesPoint { date, value }))
+            .collect();
+        Self { points }
+    }
+
+    /// The difference to the other (reference) series at the common dates.
+    pub fn diff(&self, other: &TimeSeries<T>) -> TimeSeries<T> {
+        self.zip_with(other, |v, w| Some(v - w))
+    }
+
+    /// The ratio to the other (reference) series at the common dates where the latter is non-zero.
+    pub fn ratio(&self, other: &TimeSeries<T>) -> TimeSeries<T> {
+        self.zip_with(other, |v, w| if w == 0.0 { None } else { Some(v / w) })
+    }
+
+    pub fn slice(&self, range: impl RangeBounds<T>) -> TimeSeries<T> {
+        let points = self
+            .iter()
+            .filter(|tsp| range.contains(&tsp.date))
+            .cloned()
+            .collect();
+        Self { points }
+    }
+
+    /// Removes the points with missing (`NaN`) values.
+    pub fn drop_missing(&self) -> TimeSeries<T> {
+        let points = self
+            .iter()
+            .filter(|tsp| !tsp.value.is_nan())
+            .cloned()
+            .collect();
+        Self { points }
+    }
+}
+
+impl TimeSeries {
+    /// Aggregates the values per calendar period, indexed by the first date of the period.
+    pub fn resample(&self, frequency: Frequency, aggregation: Aggregation) -> TimeSeries {
+        let mut grouped: BTreeMap<NaiveDate, Vec<f32>> = BTreeMap::new();
+        for tsp in self.iter().filter(|tsp| !tsp.value.is_nan()) {
+            grouped
+                .entry(frequency.period_start(tsp.date))
+                .or_default()
+                .push(tsp.value);
+        }
+
+        let points = grouped
+            .into_iter()
+            .flat_map(|(date, vs)| {
+                aggregation
+                    .apply(&vs)
+                    .map(|value| TimeSeriesPoint { date, value })
+            })
+            .collect();
+        Self { points }
+    }
+
+    /// Aggregates the values within the trailing window of `days` calendar days (including the date itself).
+    /// Dates before the first full window are dropped.
+    pub fn rolling(&self, days: i64, aggregation: Aggregation) -> TimeSeries {
+        let first_full_window = match self.first() {
+            Some(tsp) => tsp.date + Duration::days(days - 1),
+            None => return TimeSeries::default(),
+        };
+
+        let mut points = Vec::new();
+        let mut start = 0;
+        for (end, tsp) in self.iter().enumerate() {
+            while self.points[start].date <= tsp.date - Duration::days(days) {
+                start += 1;
+            }
+            if tsp.date < first_full_window {
+                continue;
+            }
+            let window: Vec<f32> = self.points[start..=end]
+                .iter()
+                .map(|p| p.value)
+                .filter(|v| !v.is_nan())
+                .collect();
+            if let Some(value) = aggregation.apply(&window) {
+                points.push(TimeSeriesPoint {
+                    date: tsp.date,
+                    value,
+                });
+            }
+        }
+        Self { points }
+    }
+
+    /// Fills the missing values, such that the series contains a value for each calendar day
+    /// between its first and last known value.
+    pub fn fill_missing(&self, method: FillMethod) -> TimeSeries {
+        let known = self.drop_missing();
+        let mut points = Vec::new();
+
+        for (p, q) in known.points.iter().zip(known.points.iter().skip(1)) {
+            let gap_days = (q.date - p.date).num_days();
+            for d in 0..gap_days {
+                let value = match method {
+                    FillMethod::Forward => p.value,
+                    FillMethod::Linear => {
+                        p.value + (q.value - p.value) * d as f32 / gap_days as f32
+                    }
+                };
+                points.push(TimeSeriesPoint {
+                    date: p.date + Duration::days(d),
+                    value,
+                });
+            }
+        }
+        points.extend(known.last().cloned());
+
+        Self { points }
+    }
+}
+
+impl HourlySeries {
+    /// Aggregates the values per (calendar) day.
+    pub fn daily(&self, aggregation: Aggregation) -> TimeSeries {
+        let mut grouped: BTreeMap<NaiveDate, Vec<f32>> = BTreeMap::new();
+        for tsp in self.iter().filter(|tsp| !tsp.value.is_nan()) {
+            grouped.entry(tsp.date.date()).or_default().push(tsp.value);
+        }
+        grouped
+            .into_iter()
+            .flat_map(|(date, vs)| {
+                aggregation
+                    .apply(&vs)
+                    .map(|value| TimeSeriesPoint { date, value })
+            })
+            .collect()
+    }
+}
+
+impl<T: TimeIndex> FromIterator<TimeSeriesPoint<T>> for TimeSeries<T> {
+    fn from_iter<I: IntoIterator<Item = TimeSeriesPoint<T>>>(iter: I) -> Self {
+        TimeSeries::new(iter.into_iter().collect())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a TimeSeries<T> {
+    type Item = &'a TimeSeriesPoint<T>;
+    type IntoIter = std::slice::Iter<'a, TimeSeriesPoint<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.iter()
+    }
+}
+
+/// The lead time of a forecast, i.e. the time between its as-of date and the date it is valid for.
+pub fn lead_time<T: TimeIndex>(as_of_date: T, valid_date: T) -> Duration {
+    valid_date - as_of_date
+}
+
+/// A collection of forecast curves keyed by their as-of date,
+/// i.e. each forecast value is identified by its (as-of date, valid date).
+/// The curves of sub-daily forecasts are keyed by the as-of date-time of the run (see `HourlyForecastSet`).
+#[derive(Debug, Clone)]
+pub struct ForecastSet<T = NaiveDate> {
+    curves: BTreeMap<T, TimeSeries<T>>,
+}
+
+/// A collection of hourly forecast curves keyed by their as-of (local) date-time, truncated to the hour.
+pub type HourlyForecastSet = ForecastSet<NaiveDateTime>;
+
+impl<T> Default for ForecastSet<T> {
+    fn default() -> Self {
+        Self {
+            curves: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: TimeIndex> ForecastSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the forecast curve of the as-of date, replacing a previous one of the same date.
+    pub fn insert(&mut self, as_of_date: T, curve: TimeSeries<T>) {
+        self.curves.insert(as_of_date, curve);
+    }
+
+    pub fn len(&self) -> usize {
+        self.curves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.curves.is_empty()
+    }
+
+    /// The forecast curves ordered by their as-of date.
+    pub fn iter(&self) -> btree_map::Iter<'_, T, TimeSeries<T>> {
+        self.curves.iter()
+    }
+
+    pub fn as_of_dates(&self) -> impl Iterator<Item = &T> {
+        self.curves.keys()
+    }
+
+    pub fn curve(&self, as_of_date: &T) -> Option<&TimeSeries<T>> {
+        self.curves.get(as_of_date)
+    }
+
+    pub fn get(&self, as_of_date: &T, valid_date: &T) -> Option<f32> {
+        self.curve(as_of_date).and_then(|ts| ts.get(valid_date))
+    }
+
+    /// All lead times occurring in any of the curves.
+    pub fn lead_times(&self) -> BTreeSet<Duration> {
+        self.iter()
+            .flat_map(|(as_of_date, ts)| ts.iter().map(|tsp| lead_time(*as_of_date, tsp.date)))
+            .collect()
+    }
+
+    /// The forecasts with the lead time, indexed by their valid date.
+    pub fn by_lead_time(&self, lead: Duration) -> TimeSeries<T> {
+        self.iter()
+            .flat_map(|(as_of_date, ts)| {
+                let date = *as_of_date + lead;
+                ts.get(&date).map(|value| TimeSeriesPoint { date, value })
+            })
+            .collect()
+    }
+
+    /// All forecasts for the valid date, indexed by their as-of date.
+    pub fn by_valid_date(&self, valid_date: &T) -> TimeSeries<T> {
+        self.curves
+            .range(..=*valid_date)
+            .flat_map(|(as_of_date, ts)| {
+                ts.get(valid_date).map(|value| TimeSeriesPoint {
+                    date: *as_of_date,
+                    value,
+                })
+            })
+            .collect()
+    }
+
+    /// The most recent forecast for each valid date.
+    pub fn latest(&self) -> TimeSeries<T> {
+        // for duplicate dates the time series keeps the last point, i.e. the one of the latest as-of date
+        self.iter().flat_map(|(_, ts)| ts.iter().cloned()).collect()
+    }
+
+    /// The forecasts for the as-of dates themselves, i.e. the best available approximation of the observations.
+    pub fn diagonal(&self) -> TimeSeries<T> {
+        self.by_lead_time(Duration::zero())
+    }
+
+    /// The differences of all curves to the reference curve.
+    pub fn relative_to(&self, reference: &TimeSeries<T>) -> ForecastSet<T> {
+        self.iter()
+            .map(|(as_of_date, ts)| (*as_of_date, ts.diff(reference)))
+            .collect()
+    }
+
+    /// The forecast values grouped by their lead time.
+    pub fn group_by_lead_time(&self) -> BTreeMap<Duration, Vec<f32>> {
+        let mut grouped: BTreeMap<Duration, Vec<f32>> = BTreeMap::new();
+        for (as_of_date, ts) in self.iter() {
+            for tsp in ts.iter() {
+                grouped
+                    .entry(lead_time(*as_of_date, tsp.date))
+                    .or_default()
+                    .push(tsp.value);
+            }
+        }
+        grouped
+    }
+
+    /// The forecast values grouped by their valid date, e.g. all forecasts for the same day.
+    pub fn group_by_valid_date(&self) -> BTreeMap<T, Vec<f32>> {
+        let mut grouped: BTreeMap<T, Vec<f32>> = BTreeMap::new();
+        for (_, ts) in self.iter() {
+            for tsp in ts.iter() {
+                grouped.entry(tsp.date).or_default().push(tsp.value);
+            }
+        }
+        grouped
+    }
+}
+
+impl<T: TimeIndex> FromIterator<(T, TimeSeries<T>)> for ForecastSet<T> {
+    fn from_iter<I: IntoIterator<Item = (T, TimeSeries<T>)>>(iter: I) -> Self {
+        Self {
+            curves: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ForecastSet<T> {
+    type Item = (&'a T, &'a TimeSeries<T>);
+    type IntoIter = btree_map::Iter<'a, T, TimeSeries<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.curves.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn series(points: &[(&str, f32)]) -> TimeSeries {
+        TimeSeries::new(
+            points
+                .iter()
+                .map(|(d, value)| TimeSeriesPoint {
+                    date: date(d),
+                    value: *value,
+                })
+                .collect(),
+        )
+    }
+
+    /// The points as (date, value) pairs, for comparisons with the expected points.
+    fn pairs(ts: &TimeSeries) -> Vec<(String, f32)> {
+        ts.iter()
+            .map(|tsp| (tsp.date.to_string(), tsp.value))
+            .collect()
+    }
+
+    fn expected(points: &[(&str, f32)]) -> Vec<(String, f32)> {
+        points.iter().map(|(d, v)| (d.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn new_sorts_and_keeps_the_last_duplicate() {
+        let ts = series(&[
+            ("2022-03-03", 1.0),
+            ("2022-03-01", 2.0),
+            ("2022-03-03", 3.0),
+        ]);
+        assert_eq!(
+            pairs(&ts),
+            expected(&[("2022-03-01", 2.0), ("2022-03-03", 3.0)])
+        );
+    }
+
+    #[test]
+    fn align_on_partial_overlap() {
+        let ts = series(&[
+            ("2022-03-01", 1.0),
+            ("2022-03-03", 3.0),
+            ("2022-03-04", 4.0),
+        ]);
+        let other = series(&[
+            ("2022-03-03", 30.0),
+            ("2022-03-04", 40.0),
+            ("2022-03-05", 50.0),
+        ]);
+        assert_eq!(
+            ts.align(&other),
+            vec![
+                (date("2022-03-03"), 3.0, 30.0),
+                (date("2022-03-04"), 4.0, 40.0)
+            ]
+        );
+        assert!(ts.align(&TimeSeries::default()).is_empty());
+    }
+
+    #[test]
+    fn zip_with_on_partial_overlap() {
+        let ts = series(&[
+            ("2022-03-01", 1.0),
+            ("2022-03-03", 3.0),
+            ("2022-03-04", 4.0),
+        ]);
+        let other = series(&[
+            ("2022-03-03", 2.0),
+            ("2022-03-04", 0.0),
+            ("2022-03-05", 5.0),
+        ]);
+        assert_eq!(
+            pairs(&ts.diff(&other)),
+            expected(&[("2022-03-03", 1.0), ("2022-03-04", 4.0)])
+        );
+        // the undefined ratio of the zero reference is dropped
+        assert_eq!(pairs(&ts.ratio(&other)), expected(&[("2022-03-03", 1.5)]));
+    }
+
+    #[test]
+    fn resample_at_month_boundaries() {
+        let ts = series(&[
+            ("2022-01-30", 1.0),
+            ("2022-01-31", 2.0),
+            ("2022-02-01", 3.0),
+            ("2022-02-28", f32::NAN),
+            ("2022-03-01", 4.0),
+        ]);
+        assert_eq!(
+            pairs(&ts.resample(Frequency::Monthly, Aggregation::Sum)),
+            expected(&[
+                ("2022-01-01", 3.0),
+                ("2022-02-01", 3.0),
+                ("2022-03-01", 4.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn resample_at_iso_week_boundaries() {
+        // 2022-01-02 is a Sunday, its week starts in the previous year
+        let ts = series(&[
+            ("2021-12-31", 1.0),
+            ("2022-01-02", 2.0),
+            ("2022-01-03", 3.0),
+            ("2022-01-09", 4.0),
+            ("2022-01-10", 5.0),
+        ]);
+        assert_eq!(
+            pairs(&ts.resample(Frequency::Weekly, Aggregation::Sum)),
+            expected(&[
+                ("2021-12-27", 3.0),
+                ("2022-01-03", 7.0),
+                ("2022-01-10", 5.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn rolling_with_gaps_and_partial_first_window() {
+        let ts = series(&[
+            ("2022-03-01", 1.0),
+            ("2022-03-02", 2.0),
+            ("2022-03-04", 4.0),
+            ("2022-03-05", 5.0),
+            ("2022-03-06", f32::NAN),
+            ("2022-03-08", 8.0),
+        ]);
+        // the windows of the first two days are partial, the missing days and values are skipped
+        assert_eq!(
+            pairs(&ts.rolling(3, Aggregation::Sum)),
+            expected(&[
+                ("2022-03-04", 6.0),
+                ("2022-03-05", 9.0),
+                ("2022-03-06", 9.0),
+                ("2022-03-08", 8.0)
+            ])
+        );
+        assert!(TimeSeries::default()
+            .rolling(3, Aggregation::Mean)
+            .is_empty());
+    }
+
+    #[test]
+    fn fill_missing_forward() {
+        let ts = series(&[
+            ("2022-03-01", 1.0),
+            ("2022-03-02", f32::NAN),
+            ("2022-03-04", 4.0),
+            ("2022-03-05", f32::NAN),
+        ]);
+        assert_eq!(
+            pairs(&ts.fill_missing(FillMethod::Forward)),
+            expected(&[
+                ("2022-03-01", 1.0),
+                ("2022-03-02", 1.0),
+                ("2022-03-03", 1.0),
+                ("2022-03-04", 4.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn fill_missing_linear() {
+        let ts = series(&[
+            ("2022-02-27", f32::NAN),
+            ("2022-02-28", 1.0),
+            ("2022-03-02", f32::NAN),
+            ("2022-03-03", 4.0),
+        ]);
+        assert_eq!(
+            pairs(&ts.fill_missing(FillMethod::Linear)),
+            expected(&[
+                ("2022-02-28", 1.0),
+                ("2022-03-01", 2.0),
+                ("2022-03-02", 3.0),
+                ("2022-03-03", 4.0)
+            ])
+        );
+    }
+}
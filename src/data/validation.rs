@@ -0,0 +1,230 @@
+use crate::data::football::models::FootballSeasonResults;
+use crate::data::weather::HOURLY_POINTS;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde_json::Value;
+use std::ops::RangeInclusive;
+
+/// The number of daily points of a weatherbit forecast.
+pub const EXPECTED_DAILY_POINTS: usize = 16;
+
+const TEMPERATURE_RANGE: RangeInclusive<f64> = -90.0..=60.0;
+const PROBABILITY_RANGE: RangeInclusive<f64> = 0.0..=100.0;
+const PRECIPITATION_RANGE: RangeInclusive<f64> = 0.0..=500.0;
+const MINUTES_RANGE: RangeInclusive<f64> = 0.0..=6_000.0;
+const GOALS_RANGE: RangeInclusive<f64> = 0.0..=200.0;
+const ACCURACY_RANGE: RangeInclusive<f64> = 0.0..=100.0;
+
+/// Collects the issues found in a payload, each prefixed by the location within the payload.
+#[derive(Debug, Default)]
+struct Issues(Vec<String>);
+
+impl Issues {
+    fn add(&mut self, location: &str, issue: impl std::fmt::Display) {
+        self.0.push(format!("{}: {}", location, issue));
+    }
+
+    /// Checks the number at the key of the object, if present; missing values are reported if required.
+    fn check_number(
+        &mut self,
+        location: &str,
+        object: &Value,
+        key: &str,
+        range: &RangeInclusive<f64>,
+        required: bool,
+    ) {
+        match &object[key] {
+            Value::Null if !required => {}
+            Value::Number(n) => {
+                let value = n.as_f64().unwrap_or(f64::NAN);
+                if !range.contains(&value) {
+                    self.add(
+                        location,
+                        format!("{} = {} is out of range {:?}", key, value, range),
+                    );
+                }
+            }
+            other => self.add(location, format!("{} is not a number: {}", key, other)),
+        }
+    }
+}
+
+/// Checks the forecast data of a weatherbit response: the expected number of daily points,
+/// strictly increasing valid dates and plausible values.
+/// Returns the issues found, none if the forecast is valid.
+pub fn validate_weather_forecast(forecast: &Value) -> Vec<String> {
+    let mut issues = Issues::default();
+    let points = match forecast.as_array() {
+        Some(points) => points,
+        None => {
+            issues.add("data", format!("expected an array, got {}", forecast));
+            return issues.0;
+        }
+    };
+    if points.len() != EXPECTED_DAILY_POINTS {
+        issues.add(
+            "data",
+            format!(
+                "expected {} daily points, got {}",
+                EXPECTED_DAILY_POINTS,
+                points.len()
+            ),
+        );
+    }
+
+    let mut previous_date: Option<NaiveDate> = None;
+    for (idx, point) in points.iter().enumerate() {
+        let location = format!("data[{}]", idx);
+        match point["valid_date"]
+            .as_str()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        {
+            Some(date) => {
+                if let Some(previous) = previous_date.filter(|previous| date <= *previous) {
+                    issues.add(
+                        &location,
+                        format!("valid_date {} does not follow {}", date, previous),
+                    );
+                }
+                previous_date = Some(date);
+            }
+            None => issues.add(
+                &location,
+                format!("invalid valid_date {}", point["valid_date"]),
+            ),
+        }
+        issues.check_number(&location, point, "max_temp", &TEMPERATURE_RANGE, true);
+        issues.check_number(&location, point, "min_temp", &TEMPERATURE_RANGE, true);
+        // the probability of precipitation is not offered by all forecast archives
+        issues.check_number(&location, point, "pop", &PROBABILITY_RANGE, false);
+        issues.check_number(&location, point, "precip", &PRECIPITATION_RANGE, true);
+    }
+    issues.0
+}
+
+/// Checks the forecast data of a weatherbit hourly response: the expected number of hourly points,
+/// strictly increasing hours and plausible values.
+/// The order is checked on the unix timestamps, as the local hours repeat when the daylight saving time ends.
+/// Returns the issues found, none if the forecast is valid.
+pub fn validate_hourly_weather_forecast(forecast: &Value) -> Vec<String> {
+    let mut issues = Issues::default();
+    let points = match forecast.as_array() {
+        Some(points) => points,
+        None => {
+            issues.add("data", format!("expected an array, got {}", forecast));
+            return issues.0;
+        }
+    };
+    if points.len() != HOURLY_POINTS {
+        issues.add(
+            "data",
+            format!(
+                "expected {} hourly points, got {}",
+                HOURLY_POINTS,
+                points.len()
+            ),
+        );
+    }
+
+    let mut previous_ts: Option<i64> = None;
+    for (idx, point) in points.iter().enumerate() {
+        let location = format!("data[{}]", idx);
+        match point["ts"].as_i64() {
+            Some(ts) => {
+                if let Some(previous) = previous_ts.filter(|previous| ts <= *previous) {
+                    issues.add(&location, format!("ts {} does not follow {}", ts, previous));
+                }
+                previous_ts = Some(ts);
+            }
+            None => issues.add(&location, format!("invalid ts {}", point["ts"])),
+        }
+        if point["timestamp_local"]
+            .as_str()
+            .and_then(|t| NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%S").ok())
+            .is_none()
+        {
+            issues.add(
+                &location,
+                format!("invalid timestamp_local {}", point["timestamp_local"]),
+            );
+        }
+        issues.check_number(&location, point, "temp", &TEMPERATURE_RANGE, true);
+        issues.check_number(&location, point, "pop", &PROBABILITY_RANGE, false);
+        issues.check_number(&location, point, "precip", &PRECIPITATION_RANGE, false);
+    }
+    issues.0
+}
+
+/// Checks the player statistics of a season: the structure as required for loading
+/// (see `FootballSeasonResults`) and plausible values.
+/// Returns the issues found, none if the statistics are valid.
+pub fn validate_football_results(season_results: &Value) -> Vec<String> {
+    let mut issues = Issues::default();
+    if let Err(e) = serde_json::from_value::<FootballSeasonResults>(season_results.clone()) {
+        issues.add("season", format!("cannot be loaded: {}", e));
+        return issues.0;
+    }
+
+    let player_results: &[Value] = season_results["playerResults"]
+        .as_array()
+        .map(|results| results.as_slice())
+        .unwrap_or_default();
+    if player_results.is_empty() {
+        issues.add("playerResults", "no player statistics");
+    }
+
+    for (idx, result) in player_results.iter().enumerate() {
+        if result["player"]["id"].as_i64().is_none() {
+            issues.add(&format!("playerResults[{}]", idx), "missing player id");
+        }
+        for (stats_idx, stats) in result["statistics"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            let location = format!("playerResults[{}].statistics[{}]", idx, stats_idx);
+            issues.check_number(&location, &stats["games"], "minutes", &MINUTES_RANGE, false);
+            issues.check_number(&location, &stats["goals"], "total", &GOALS_RANGE, false);
+            issues.check_number(
+                &location,
+                &stats["passes"],
+                "accuracy",
+                &ACCURACY_RANGE,
+                false,
+            );
+        }
+    }
+    issues.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn daily_points(count: usize) -> Value {
+        let start = NaiveDate::from_ymd_opt(2022, 7, 1).unwrap();
+        (0..count)
+            .map(|idx| {
+                json!({
+                    "valid_date": (start + chrono::Duration::days(idx as i64)).to_string(),
+                    "max_temp": 25.0,
+                    "min_temp": 15.0,
+                    "pop": 20,
+                    "precip": 0.0,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accepts_a_full_forecast() {
+        assert!(validate_weather_forecast(&daily_points(EXPECTED_DAILY_POINTS)).is_empty());
+    }
+
+    #[test]
+    fn rejects_a_short_forecast() {
+        let issues = validate_weather_forecast(&daily_points(10));
+        assert_eq!(issues, vec!["data: expected 16 daily points, got 10"]);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::data::http::HttpClient;
+use crate::data::validation::EXPECTED_DAILY_POINTS;
+use crate::data::weather::models::WeatherResponse;
+use crate::data::weather::WeatherLocation;
+use crate::errors::AppError;
+use chrono::{NaiveDate, Offset};
+use serde_json::{json, Value};
+use std::result::Result;
+
+/// The Open-Meteo endpoint of past forecast runs, queried by the time of the run.
+pub const OPEN_METEO_RUNS_URL: &str = "https://single-runs-api.open-meteo.com/v1/forecast";
+/// The Open-Meteo endpoint resolving postal codes to coordinates.
+pub const OPEN_METEO_GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1/search";
+/// The name of the archive within the metadata of the backfilled forecasts.
+const OPEN_METEO: &str = "open-meteo";
+/// The GFS model covers the 16 days of the weatherbit forecasts.
+const OPEN_METEO_MODEL: &str = "gfs_seamless";
+/// The daily variables of Open-Meteo along with the corresponding fields of the weatherbit forecast points.
+const DAILY_VARIABLES: [(&str, &str); 4] = [
+    ("temperature_2m_max", "max_temp"),
+    ("temperature_2m_min", "min_temp"),
+    ("precipitation_probability_max", "pop"),
+    ("precipitation_sum", "precip"),
+];
+
+/// An archive of past forecast runs (see `OPEN_METEO_RUNS_URL`), used to backfill the forecasts
+/// of as-of dates before the first daily run.
+#[derive(Debug, Clone)]
+pub struct ForecastArchive {
+    pub url: String,
+    pub geocoding_url: String,
+}
+
+impl Default for ForecastArchive {
+    fn default() -> Self {
+        Self {
+            url: OPEN_METEO_RUNS_URL.to_string(),
+            geocoding_url: OPEN_METEO_GEOCODING_URL.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl ForecastArchive {
+    /// Resolves the postal code of the location to its coordinates, as required by the archive.
+    pub fn coordinates(
+        &self,
+        client: &HttpClient,
+        location: &WeatherLocation,
+    ) -> Result<Coordinates, AppError> {
+        geocode(client, &self.geocoding_url, location)
+    }
+
+    /// Loads the forecast run at midnight (UTC) of the as-of date, with the daily values in the timezone of the location.
+    /// The forecast is converted into the daily points of the weatherbit forecasts, marked as backfilled.
+    pub fn load_weather_data(
+        &self,
+        client: &HttpClient,
+        location: &WeatherLocation,
+        coordinates: &Coordinates,
+        as_of_date: &NaiveDate,
+    ) -> Result<WeatherResponse, AppError> {
+        let daily: Vec<&str> = DAILY_VARIABLES.iter().map(|(name, _)| *name).collect();
+        let query = [
+            ("latitude", coordinates.latitude.to_string()),
+            ("longitude", coordinates.longitude.to_string()),
+            ("run", format!("{}T00:00", as_of_date.format("%Y-%m-%d"))),
+            ("models", OPEN_METEO_MODEL.to_string()),
+            ("daily", daily.join(",")),
+            ("forecast_days", EXPECTED_DAILY_POINTS.to_string()),
+            ("timezone", location.timezone.name().to_string()),
+        ];
+        let resp_json = client.get_json(&self.url, &query, &[])?;
+
+        let fetched_at = location.now();
+        let response = WeatherResponse {
+            as_of_date: *as_of_date,
+            fetched_at: Some(fetched_at.with_timezone(&fetched_at.offset().fix())),
+            backfilled: true,
+            source: Some(OPEN_METEO.to_string()),
+            member: None,
+            forecast: daily_points(&resp_json["daily"]),
+        };
+        Ok(response)
+    }
+}
+
+/// Resolves the postal code of the location to its coordinates by the geocoding API (see `OPEN_METEO_GEOCODING_URL`).
+pub(super) fn geocode(
+    client: &HttpClient,
+    geocoding_url: &str,
+    location: &WeatherLocation,
+) -> Result<Coordinates, AppError> {
+    let query = [
+        ("name", location.zip.clone()),
+        ("countryCode", location.country_code.clone()),
+        ("count", "1".to_string()),
+    ];
+    let resp_json = client.get_json(geocoding_url, &query, &[])?;
+    let result = &resp_json["results"][0];
+    match (result["latitude"].as_f64(), result["longitude"].as_f64()) {
+        (Some(latitude), Some(longitude)) => Ok(Coordinates {
+            latitude,
+            longitude,
+        }),
+        _ => Err(AppError::InvalidPayload(format!(
+            "no coordinates found for {}",
+            location
+        ))),
+    }
+}
+
+/// Converts the daily columns of Open-Meteo into the daily points of weatherbit, e.g.
+/// `{"time": ["2022-07-25", ..], "temperature_2m_max": [33.6, ..]}` into `[{"valid_date": "2022-07-25", "max_temp": 33.6}, ..]`.
+/// Missing values are omitted, the columns are kept as is if they are malformed such that the validation reports them.
+fn daily_points(daily: &Value) -> Value {
+    let dates = match daily["time"].as_array() {
+        Some(dates) => dates,
+        None => return daily.clone(),
+    };
+    let points: Vec<Value> = dates
+        .iter()
+        .enumerate()
+        .map(|(idx, date)| {
+            let mut point = json!({ "valid_date": date });
+            for (name, field) in DAILY_VARIABLES.iter() {
+                let value = &daily[*name][idx];
+                if !value.is_null() {
+                    point[*field] = value.clone();
+                }
+            }
+            point
+        })
+        .collect();
+    Value::Array(points)
+}
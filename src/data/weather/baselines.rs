@@ -0,0 +1,169 @@
+use crate::data::models::{lead_time, ForecastSet, TimeSeries, TimeSeriesPoint};
+use crate::errors::AppError;
+use chrono::{Datelike, Duration, NaiveDate};
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::result::Result;
+
+/// The naive forecasts the weather forecasts are compared against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Baseline {
+    /// The forecast for all days ahead equals the observation of the as-of date
+    #[clap(name = "persistence")]
+    Persistence,
+    /// The forecast equals the average observation of the same calendar day in other years
+    #[clap(name = "climatology")]
+    Climatology,
+    /// The forecast equals the forecast of the previous as-of date for the same day
+    #[clap(name = "previous_forecast")]
+    PreviousForecast,
+}
+
+impl Baseline {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Baseline::Persistence => "persistence",
+            Baseline::Climatology => "climatology",
+            Baseline::PreviousForecast => "previous_forecast",
+        }
+    }
+}
+
+impl std::fmt::Display for Baseline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The observed values per calendar day (month, day) and year.
+#[derive(Debug, Clone, Default)]
+pub struct Climatology {
+    observations: BTreeMap<(u32, u32), Vec<(i32, f32)>>,
+}
+
+impl Climatology {
+    pub fn from_observations(observations: &TimeSeries) -> Self {
+        let mut climatology = Self::default();
+        for tsp in observations.drop_missing().iter() {
+            climatology
+                .observations
+                .entry((tsp.date.month(), tsp.date.day()))
+                .or_default()
+                .push((tsp.date.year(), tsp.value));
+        }
+        climatology
+    }
+
+    /// Reads the observations from a CSV file with the columns 'date' (formatted as '%Y-%m-%d') and 'value'.
+    pub fn from_csv(file_path: &Path) -> Result<Self, AppError> {
+        let df = CsvReader::from_path(file_path)?.has_header(true).finish()?;
+        let dates = df.column("date")?.utf8()?;
+        let values = df.column("value")?.cast(&DataType::Float32)?;
+        let values = values.f32()?;
+
+        let observations: TimeSeries = dates
+            .into_iter()
+            .zip(values)
+            .flat_map(|(date, value)| {
+                let date = NaiveDate::parse_from_str(date?, "%Y-%m-%d").ok()?;
+                Some(TimeSeriesPoint {
+                    date,
+                    value: value?,
+                })
+            })
+            .collect();
+        Ok(Self::from_observations(&observations))
+    }
+
+    /// Merges the observations of both, the other's observations take precedence for the same date.
+    pub fn merge(mut self, other: &Climatology) -> Self {
+        for (day, observations) in other.observations.iter() {
+            let merged = self.observations.entry(*day).or_default();
+            merged.retain(|(year, _)| observations.iter().all(|(y, _)| y != year));
+            merged.extend(observations.iter().cloned());
+        }
+        self
+    }
+
+    /// The average observation of the calendar day of the date in all other years,
+    /// such that the observation of the date itself never enters its climatology.
+    pub fn get(&self, date: &NaiveDate) -> Option<f32> {
+        let values: Vec<f32> = self
+            .observations
+            .get(&(date.month(), date.day()))?
+            .iter()
+            .filter(|(year, _)| *year != date.year())
+            .map(|(_, value)| *value)
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(values.iter().sum::<f32>() / values.len() as f32)
+    }
+}
+
+/// The forecasts of the baseline for the same (as-of date, valid date) as the forecasts.
+/// The observations are approximated by the forecasts for the as-of dates themselves (the diagonal).
+pub fn baseline_forecasts(
+    forecasts: &ForecastSet,
+    baseline: Baseline,
+    climatology: &Climatology,
+) -> ForecastSet {
+    let observed = forecasts.diagonal();
+
+    forecasts
+        .iter()
+        .map(|(as_of_date, ts)| {
+            let baseline_ts: TimeSeries = ts
+                .iter()
+                .flat_map(|tsp| {
+                    let value = match baseline {
+                        Baseline::Persistence => observed.get(as_of_date),
+                        Baseline::Climatology => climatology.get(&tsp.date),
+                        Baseline::PreviousForecast => {
+                            forecasts.get(&(*as_of_date - Duration::days(1)), &tsp.date)
+                        }
+                    }?;
+                    Some(TimeSeriesPoint {
+                        date: tsp.date,
+                        value,
+                    })
+                })
+                .collect();
+            (*as_of_date, baseline_ts)
+        })
+        .collect()
+}
+
+/// The mean absolute error skill score `1 - MAE(forecast) / MAE(baseline)` per lead time (excluding the as-of date),
+/// evaluated on the forecasts for which the baseline is available.
+/// A positive score means the forecast beats the baseline, 1 is a perfect forecast.
+pub fn skill_scores(forecasts: &ForecastSet, baseline: &ForecastSet) -> Vec<(Duration, f32)> {
+    let observed = forecasts.diagonal();
+    // the absolute errors of the forecast and of the baseline per lead time
+    let mut errors_by_d: BTreeMap<Duration, (f32, f32)> = BTreeMap::new();
+
+    for (as_of_date, ts) in forecasts.iter() {
+        for tsp in ts.iter() {
+            let days_ahead = lead_time(*as_of_date, tsp.date);
+            if days_ahead <= Duration::zero() {
+                continue;
+            }
+            let (obs, baseline_value) =
+                match (observed.get(&tsp.date), baseline.get(as_of_date, &tsp.date)) {
+                    (Some(obs), Some(baseline_value)) => (obs, baseline_value),
+                    _ => continue,
+                };
+            let errors = errors_by_d.entry(days_ahead).or_insert((0.0, 0.0));
+            errors.0 += (tsp.value - obs).abs();
+            errors.1 += (baseline_value - obs).abs();
+        }
+    }
+
+    errors_by_d
+        .into_iter()
+        .filter(|(_, (_, baseline_error))| *baseline_error > 0.0)
+        .map(|(days_ahead, (error, baseline_error))| (days_ahead, 1.0 - error / baseline_error))
+        .collect()
+}
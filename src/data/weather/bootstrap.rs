@@ -0,0 +1,293 @@
+use crate::data::models::{lead_time, ForecastSet, TimeSeries};
+use crate::data::weather::conversions::level_value;
+use chrono::{Duration, NaiveDate};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
+
+/// The minimum number of samples per lead time to compute a statistic, see `percentiles`.
+const MIN_SAMPLES: usize = 5;
+
+/// The settings of the moving block bootstrap over the as-of dates.
+/// The as-of dates are resampled in blocks of consecutive (calendar) dates, as the errors of successive forecasts
+/// are correlated; blocks end early at missing dates, see `resample_indices`.
+#[derive(Debug, Clone)]
+pub struct BootstrapConfig {
+    pub resamples: usize,
+    pub block_length: usize,
+    /// The confidence level of the intervals, e.g. 0.95 for the 2.5% and 97.5% quantiles of the resampled statistics
+    pub confidence: f32,
+    pub seed: u64,
+}
+
+impl Default for BootstrapConfig {
+    fn default() -> Self {
+        Self {
+            resamples: 500,
+            block_length: 3,
+            confidence: 0.9,
+            seed: 42,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConfidenceInterval {
+    /// The statistic of the original sample
+    pub estimate: f32,
+    pub lower: f32,
+    pub upper: f32,
+}
+
+/// The statistics of the forecast errors per lead time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorStatistic {
+    Bias,
+    MeanAbsolute,
+    RootMeanSquare,
+    /// The percentile level (in percent) of the errors
+    Quantile(usize),
+}
+
+impl ErrorStatistic {
+    pub fn name(&self) -> String {
+        match self {
+            ErrorStatistic::Bias => "bias".to_string(),
+            ErrorStatistic::MeanAbsolute => "mae".to_string(),
+            ErrorStatistic::RootMeanSquare => "rmse".to_string(),
+            ErrorStatistic::Quantile(level) => format!("level {}%", level),
+        }
+    }
+
+    fn apply(&self, errors: &mut [f32]) -> Option<f32> {
+        if errors.len() < MIN_SAMPLES {
+            return None;
+        }
+        let n = errors.len() as f32;
+        let value = match self {
+            ErrorStatistic::Bias => errors.iter().sum::<f32>() / n,
+            ErrorStatistic::MeanAbsolute => errors.iter().map(|e| e.abs()).sum::<f32>() / n,
+            ErrorStatistic::RootMeanSquare => {
+                (errors.iter().map(|e| e * e).sum::<f32>() / n).sqrt()
+            }
+            ErrorStatistic::Quantile(level) => {
+                errors.sort_by(|a, b| a.total_cmp(b));
+                level_value(errors, *level)
+            }
+        };
+        Some(value)
+    }
+}
+
+/// The forecast errors relative to the reference curve, per as-of date and lead time.
+pub type ForecastErrors = BTreeMap<NaiveDate, Vec<(Duration, f32)>>;
+
+pub fn forecast_errors(forecasts: &ForecastSet, reference: &TimeSeries) -> ForecastErrors {
+    forecasts
+        .relative_to(reference)
+        .iter()
+        .map(|(as_of_date, ts)| {
+            let errors = ts
+                .iter()
+                .map(|tsp| (lead_time(*as_of_date, tsp.date), tsp.value))
+                .collect();
+            (*as_of_date, errors)
+        })
+        .collect()
+}
+
+/// The statistic per lead time of the errors of the given as-of dates (with repetitions).
+fn statistic_by_lead_time<'a>(
+    errors: impl IntoIterator<Item = &'a Vec<(Duration, f32)>>,
+    statistic: ErrorStatistic,
+) -> BTreeMap<Duration, f32> {
+    let mut grouped_by_d: BTreeMap<Duration, Vec<f32>> = BTreeMap::new();
+    for as_of_errors in errors {
+        for (days_ahead, error) in as_of_errors.iter() {
+            grouped_by_d.entry(*days_ahead).or_default().push(*error);
+        }
+    }
+    grouped_by_d
+        .into_iter()
+        .flat_map(|(days_ahead, mut vs)| statistic.apply(&mut vs).map(|v| (days_ahead, v)))
+        .collect()
+}
+
+/// Draws as many as-of dates (indices of the sorted dates) as there are, by concatenating blocks of consecutive dates
+/// starting at random positions. A block ends early at a missing date or the last date, i.e. it never spans a gap.
+fn resample_indices(dates: &[NaiveDate], block_length: usize, rng: &mut StdRng) -> Vec<usize> {
+    let n = dates.len();
+    let block_length = block_length.max(1);
+    let mut indices = Vec::with_capacity(n + block_length);
+    while indices.len() < n {
+        let start = rng.gen_range(0..n);
+        indices.extend(start..block_end(dates, start, block_length));
+    }
+    indices.truncate(n);
+    indices
+}
+
+/// The (exclusive) end of the block starting at the index, i.e. of up to `block_length` consecutive dates.
+fn block_end(dates: &[NaiveDate], start: usize, block_length: usize) -> usize {
+    let mut end = start + 1;
+    while end < dates.len()
+        && end - start < block_length
+        && dates[end] - dates[end - 1] == Duration::days(1)
+    {
+        end += 1;
+    }
+    end
+}
+
+/// The quantile `q` (0-1) of the sorted values, interpolated linearly between the closest ranks.
+fn sample_quantile(sorted_values: &[f32], q: f32) -> f32 {
+    let position = q.clamp(0.0, 1.0) * (sorted_values.len() - 1) as f32;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    let fraction = position - lower as f32;
+    sorted_values[lower] + (sorted_values[upper] - sorted_values[lower]) * fraction
+}
+
+/// The confidence intervals of the statistic of the errors per lead time.
+pub fn bootstrap_intervals(
+    errors: &ForecastErrors,
+    statistic: ErrorStatistic,
+    config: &BootstrapConfig,
+) -> Vec<(Duration, ConfidenceInterval)> {
+    let estimates = statistic_by_lead_time(errors.values(), statistic);
+    let as_of_dates: Vec<NaiveDate> = errors.keys().cloned().collect();
+    let as_of_errors: Vec<&Vec<(Duration, f32)>> = errors.values().collect();
+    if as_of_errors.is_empty() {
+        return Vec::new();
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut resampled_by_d: BTreeMap<Duration, Vec<f32>> = BTreeMap::new();
+    for _ in 0..config.resamples {
+        let indices = resample_indices(&as_of_dates, config.block_length, &mut rng);
+        let resampled = statistic_by_lead_time(indices.iter().map(|&i| as_of_errors[i]), statistic);
+        for (days_ahead, value) in resampled {
+            resampled_by_d.entry(days_ahead).or_default().push(value);
+        }
+    }
+
+    let tail = (1.0 - config.confidence) / 2.0;
+    estimates
+        .into_iter()
+        .flat_map(|(days_ahead, estimate)| {
+            let resampled = resampled_by_d.get_mut(&days_ahead)?;
+            resampled.sort_by(|a, b| a.total_cmp(b));
+            Some((
+                days_ahead,
+                ConfidenceInterval {
+                    estimate,
+                    lower: sample_quantile(resampled, tail),
+                    upper: sample_quantile(resampled, 1.0 - tail),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// The confidence intervals of the percentile levels of the errors relative to the reference (diagonal) curve,
+/// see `percentiles`.
+pub fn percentile_intervals(
+    forecasts: &ForecastSet,
+    config: &BootstrapConfig,
+) -> HashMap<usize, Vec<(Duration, ConfidenceInterval)>> {
+    let errors = forecast_errors(forecasts, &forecasts.diagonal());
+    (20..=80)
+        .step_by(20)
+        .map(|level| {
+            (
+                level,
+                bootstrap_intervals(&errors, ErrorStatistic::Quantile(level), config),
+            )
+        })
+        .collect()
+}
+
+pub fn print_error_intervals(
+    statistic: ErrorStatistic,
+    intervals: &[(Duration, ConfidenceInterval)],
+) {
+    println!(
+        "{:>10} | {:>10} | {:>8} | {:>8}",
+        "days ahead",
+        statistic.name(),
+        "lower",
+        "upper"
+    );
+    for (days_ahead, interval) in intervals.iter() {
+        println!(
+            "{:>10} | {:>10.2} | {:>8.2} | {:>8.2}",
+            days_ahead.num_days(),
+            interval.estimate,
+            interval.lower,
+            interval.upper
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant_errors(dates: &[NaiveDate], error: f32) -> ForecastErrors {
+        dates
+            .iter()
+            .map(|date| {
+                let errors = (1..=3).map(|d| (Duration::days(d), error)).collect();
+                (*date, errors)
+            })
+            .collect()
+    }
+
+    fn days(from: &str, n: i64) -> Vec<NaiveDate> {
+        let from = NaiveDate::parse_from_str(from, "%Y-%m-%d").unwrap();
+        (0..n).map(|d| from + Duration::days(d)).collect()
+    }
+
+    #[test]
+    fn sample_quantile_interpolates_fractional_levels() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!((sample_quantile(&values, 0.025) - 1.1).abs() < 1e-6);
+        assert!((sample_quantile(&values, 0.5) - 3.0).abs() < 1e-6);
+        assert!((sample_quantile(&values, 0.975) - 4.9).abs() < 1e-6);
+        assert_eq!(sample_quantile(&[7.0], 0.05), 7.0);
+    }
+
+    #[test]
+    fn bootstrap_bounds_of_constant_errors() {
+        let errors = constant_errors(&days("2022-07-01", 10), 1.5);
+        let config = BootstrapConfig {
+            resamples: 50,
+            confidence: 0.95,
+            ..BootstrapConfig::default()
+        };
+        for statistic in [ErrorStatistic::Bias, ErrorStatistic::MeanAbsolute] {
+            let intervals = bootstrap_intervals(&errors, statistic, &config);
+            assert_eq!(intervals.len(), 3);
+            for (_, interval) in intervals.iter() {
+                assert!((interval.estimate - 1.5).abs() < 1e-6);
+                assert!((interval.lower - 1.5).abs() < 1e-6);
+                assert!((interval.upper - 1.5).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_do_not_span_missing_dates() {
+        // two runs of three consecutive dates, separated by a gap
+        let mut dates = days("2022-07-01", 3);
+        dates.extend(days("2022-07-10", 3));
+        assert_eq!(block_end(&dates, 0, 2), 2);
+        assert_eq!(block_end(&dates, 1, 6), 3);
+        assert_eq!(block_end(&dates, 3, 6), 6);
+        assert_eq!(block_end(&dates, 5, 6), 6);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let indices = resample_indices(&dates, 3, &mut rng);
+        assert_eq!(indices.len(), dates.len());
+        assert!(indices.iter().all(|idx| *idx < dates.len()));
+    }
+}
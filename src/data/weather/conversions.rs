@@ -1,14 +1,13 @@
-use crate::data::models::{TimeSeries, TimeSeriesPoint};
-use crate::data::weather::models::{ForecastCurve, WeatherResponse};
-use chrono::Utc;
-use chrono::{Date, Duration};
+use crate::data::models::{ForecastSet, TimeIndex, TimeSeries, TimeSeriesPoint};
+use crate::data::weather::models::{ForecastCurve, PrecipitationCurve, WeatherResponse};
+use chrono::Duration;
 use std::collections::HashMap;
 
 // TODO: refactor to make it more clear what and where the conversions happen
 pub fn convert_weather_responses<T: serde::de::DeserializeOwned>(
     forecasts: Vec<WeatherResponse>,
     create_time_series: impl Fn(T) -> TimeSeries,
-) -> Vec<(Date<Utc>, TimeSeries)> {
+) -> ForecastSet {
     forecasts
         .into_iter()
         .flat_map(|fc| {
@@ -23,19 +22,40 @@ pub fn create_temperature_timeseries(forecast_curve: ForecastCurve) -> TimeSerie
     forecast_curve.iter().map(|fc_pt| fc_pt.into()).collect()
 }
 
-pub fn reference_timeseries(forecast_ts: &[(Date<Utc>, TimeSeries)]) -> Vec<TimeSeriesPoint> {
-    forecast_ts.iter().map(|(_, ts)| ts[0].clone()).collect()
+/// The probability of precipitation as a fraction (0-1), skipping the points without probability.
+pub fn create_precipitation_probability_timeseries(
+    forecast_curve: PrecipitationCurve,
+) -> TimeSeries {
+    forecast_curve
+        .iter()
+        .flat_map(|fc_pt| {
+            Some(TimeSeriesPoint {
+                date: fc_pt.valid_date,
+                value: fc_pt.pop? / 100.0,
+            })
+        })
+        .collect()
 }
 
-pub fn percentile_timeseries(forecast_ts: &[(Date<Utc>, TimeSeries)]) -> Vec<(usize, TimeSeries)> {
-    let mut grouped_by_date: HashMap<Date<Utc>, Vec<f32>> = HashMap::new();
+/// The accumulated precipitation in mm.
+pub fn create_precipitation_timeseries(forecast_curve: PrecipitationCurve) -> TimeSeries {
+    forecast_curve
+        .iter()
+        .map(|fc_pt| TimeSeriesPoint {
+            date: fc_pt.valid_date,
+            value: fc_pt.precip,
+        })
+        .collect()
+}
 
-    for (_, ts) in forecast_ts.iter() {
-        for tsp in ts.iter() {
-            let vs = grouped_by_date.entry(tsp.date).or_insert(Vec::new());
-            vs.push(tsp.value);
-        }
-    }
+/// The value at the percentile level (in percent) of the sorted values.
+pub(super) fn level_value(sorted_values: &[f32], level: usize) -> f32 {
+    let idx = ((sorted_values.len() as f32) * (level as f32 / 100.0)).floor() as usize;
+    sorted_values[idx.min(sorted_values.len() - 1)]
+}
+
+pub fn percentile_timeseries(forecasts: &ForecastSet) -> Vec<(usize, TimeSeries)> {
+    let mut grouped_by_date = forecasts.group_by_valid_date();
 
     grouped_by_date
         .iter_mut()
@@ -45,42 +65,32 @@ pub fn percentile_timeseries(forecast_ts: &[(Date<Utc>, TimeSeries)]) -> Vec<(us
     let mut level_map = Vec::with_capacity(4);
 
     for level in (20..=80).step_by(20) {
-        let mut level_curve = Vec::new();
-        for (&date, vs) in grouped_by_date.iter() {
-            if vs.len() >= max_idx {
-                let idx = ((vs.len() as f32) * (level as f32 / 100.0)).floor() as usize;
-                level_curve.push(TimeSeriesPoint {
-                    date,
-                    value: vs[idx],
-                });
-            }
-        }
-
-        level_curve.sort_by_key(|d| d.date);
+        let level_curve: TimeSeries = grouped_by_date
+            .iter()
+            .filter(|(_, vs)| vs.len() >= max_idx)
+            .map(|(&date, vs)| TimeSeriesPoint {
+                date,
+                value: level_value(vs, level),
+            })
+            .collect();
         level_map.push((level, level_curve));
     }
 
     level_map
 }
 
-pub fn percentiles(
-    ref_curve: &[TimeSeriesPoint],
-    forecast_ts: &[(Date<Utc>, TimeSeries)],
+/// The percentile levels of the forecast errors per lead time, relative to the reference (diagonal) curve.
+pub fn percentiles(forecasts: &ForecastSet) -> HashMap<usize, Vec<(Duration, f32)>> {
+    percentiles_relative_to(forecasts, &forecasts.diagonal())
+}
+
+/// The percentile levels of the differences to the reference curve per lead time,
+/// e.g. of the hourly forecasts relative to the latest ones.
+pub fn percentiles_relative_to<T: TimeIndex>(
+    forecasts: &ForecastSet<T>,
+    reference: &TimeSeries<T>,
 ) -> HashMap<usize, Vec<(Duration, f32)>> {
-    let ref_by_date: HashMap<Date<Utc>, f32> =
-        ref_curve.iter().map(|tsp| (tsp.date, tsp.value)).collect();
-
-    let mut grouped_by_d: HashMap<Duration, Vec<f32>> = HashMap::new();
-
-    for (as_of_date, ts) in forecast_ts.iter() {
-        for tsp in ts.iter() {
-            if let Some(ref_v) = ref_by_date.get(&tsp.date) {
-                let days_ahead = tsp.date - *as_of_date;
-                let vs = grouped_by_d.entry(days_ahead).or_insert(Vec::new());
-                vs.push(tsp.value - ref_v);
-            }
-        }
-    }
+    let mut grouped_by_d = forecasts.relative_to(reference).group_by_lead_time();
 
     grouped_by_d
         .iter_mut()
@@ -91,15 +101,11 @@ pub fn percentiles(
     let mut level_map = HashMap::new();
 
     for level in (20..=80).step_by(20) {
-        let mut level_curve = Vec::new();
-        for (duration, vs) in grouped_by_d.iter() {
-            if vs.len() >= max_idx {
-                let idx = ((vs.len() as f32) * (level as f32 / 100.0)).floor() as usize;
-                level_curve.push((*duration, vs[idx]));
-            }
-        }
-
-        level_curve.sort_by(|(d1, _), (d2, _)| d1.cmp(d2));
+        let level_curve: Vec<(Duration, f32)> = grouped_by_d
+            .iter()
+            .filter(|(_, vs)| vs.len() >= max_idx)
+            .map(|(duration, vs)| (*duration, level_value(vs, level)))
+            .collect();
         level_map.insert(level, level_curve);
     }
 
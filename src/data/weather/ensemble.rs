@@ -0,0 +1,251 @@
+use crate::data::http::HttpClient;
+use crate::data::models::{lead_time, Aggregation, HourlySeries, TimeSeries, TimeSeriesPoint};
+use crate::data::validation::EXPECTED_DAILY_POINTS;
+use crate::data::weather::archive::{geocode, Coordinates, OPEN_METEO_GEOCODING_URL};
+use crate::data::weather::models::WeatherResponse;
+use crate::data::weather::WeatherLocation;
+use crate::errors::AppError;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Offset};
+use chrono_tz::Tz;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::result::Result;
+
+/// The Open-Meteo endpoint of the ensemble forecasts, providing the hourly values of each member.
+pub const OPEN_METEO_ENSEMBLE_URL: &str = "https://ensemble-api.open-meteo.com/v1/ensemble";
+/// The name of the ensemble source within the metadata of the members.
+const OPEN_METEO_ENSEMBLE: &str = "open-meteo-ensemble";
+/// The GFS ensemble (31 members) covers the 16 days of the weatherbit forecasts.
+const ENSEMBLE_MODEL: &str = "gfs_seamless";
+const TEMPERATURE: &str = "temperature_2m";
+const PRECIPITATION: &str = "precipitation";
+
+/// The API of the ensemble forecasts (see `OPEN_METEO_ENSEMBLE_URL`).
+#[derive(Debug, Clone)]
+pub struct EnsembleApi {
+    pub url: String,
+    pub geocoding_url: String,
+}
+
+impl Default for EnsembleApi {
+    fn default() -> Self {
+        Self {
+            url: OPEN_METEO_ENSEMBLE_URL.to_string(),
+            geocoding_url: OPEN_METEO_GEOCODING_URL.to_string(),
+        }
+    }
+}
+
+impl EnsembleApi {
+    pub fn coordinates(
+        &self,
+        client: &HttpClient,
+        location: &WeatherLocation,
+    ) -> Result<Coordinates, AppError> {
+        geocode(client, &self.geocoding_url, location)
+    }
+
+    /// Loads the current ensemble forecast, one response per member (the control run being member 0).
+    /// The hourly values of the members are aggregated into the daily points of the weatherbit forecasts.
+    pub fn load_members(
+        &self,
+        client: &HttpClient,
+        location: &WeatherLocation,
+        coordinates: &Coordinates,
+        as_of: &DateTime<Tz>,
+    ) -> Result<Vec<WeatherResponse>, AppError> {
+        let query = [
+            ("latitude", coordinates.latitude.to_string()),
+            ("longitude", coordinates.longitude.to_string()),
+            ("models", ENSEMBLE_MODEL.to_string()),
+            ("hourly", format!("{},{}", TEMPERATURE, PRECIPITATION)),
+            ("forecast_days", EXPECTED_DAILY_POINTS.to_string()),
+            ("timezone", location.timezone.name().to_string()),
+        ];
+        let resp_json = client.get_json(&self.url, &query, &[])?;
+        let hourly = &resp_json["hourly"];
+
+        let members = member_series(hourly, TEMPERATURE);
+        if members.is_empty() {
+            return Err(AppError::InvalidPayload(format!(
+                "no ensemble members in {}",
+                hourly
+            )));
+        }
+        let precipitation = member_series(hourly, PRECIPITATION);
+
+        let responses = members
+            .iter()
+            .map(|(member, temperature)| WeatherResponse {
+                as_of_date: as_of.naive_local().date(),
+                fetched_at: Some(as_of.with_timezone(&as_of.offset().fix())),
+                backfilled: false,
+                source: Some(OPEN_METEO_ENSEMBLE.to_string()),
+                member: Some(*member),
+                forecast: daily_points(temperature, precipitation.get(member)),
+            })
+            .collect();
+        Ok(responses)
+    }
+}
+
+/// The hourly series of the variable per member, e.g. of the columns 'temperature_2m' (member 0)
+/// and 'temperature_2m_member01' to 'temperature_2m_member30'.
+fn member_series(hourly: &Value, variable: &str) -> BTreeMap<usize, HourlySeries> {
+    let times: Vec<Option<NaiveDateTime>> = hourly["time"]
+        .as_array()
+        .map(|times| {
+            times
+                .iter()
+                .map(|t| {
+                    t.as_str()
+                        .and_then(|t| NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M").ok())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut members = BTreeMap::new();
+    for (key, values) in hourly.as_object().into_iter().flatten() {
+        let member = match key.strip_prefix(variable) {
+            Some("") => 0,
+            Some(suffix) => match suffix
+                .strip_prefix("_member")
+                .and_then(|m| m.parse::<usize>().ok())
+            {
+                Some(member) => member,
+                None => continue,
+            },
+            None => continue,
+        };
+        let series: HourlySeries = times
+            .iter()
+            .zip(values.as_array().into_iter().flatten())
+            .flat_map(|(time, value)| {
+                Some(TimeSeriesPoint {
+                    date: (*time)?,
+                    value: value.as_f64()? as f32,
+                })
+            })
+            .collect();
+        members.insert(member, series);
+    }
+    members
+}
+
+/// The daily points of a member, i.e. the maximal and minimal temperature and the accumulated precipitation per day.
+fn daily_points(temperature: &HourlySeries, precipitation: Option<&HourlySeries>) -> Value {
+    let max_temp = temperature.daily(Aggregation::Max);
+    let min_temp = temperature.daily(Aggregation::Min);
+    let precip = precipitation
+        .map(|p| p.daily(Aggregation::Sum))
+        .unwrap_or_default();
+
+    let points: Vec<Value> = max_temp
+        .iter()
+        .map(|tsp| {
+            let mut point = json!({
+                "valid_date": tsp.date.format("%Y-%m-%d").to_string(),
+                "max_temp": tsp.value,
+            });
+            if let Some(min) = min_temp.get(&tsp.date) {
+                point["min_temp"] = json!(min);
+            }
+            if let Some(precip) = precip.get(&tsp.date) {
+                point["precip"] = json!(precip);
+            }
+            point
+        })
+        .collect();
+    Value::Array(points)
+}
+
+/// The curves of the members of an ensemble forecast as of a date.
+#[derive(Debug, Clone)]
+pub struct EnsembleForecast {
+    pub as_of_date: NaiveDate,
+    pub members: BTreeMap<usize, TimeSeries>,
+}
+
+impl EnsembleForecast {
+    /// The values of all members per valid date.
+    fn group_by_valid_date(&self) -> BTreeMap<NaiveDate, Vec<f32>> {
+        let mut grouped: BTreeMap<NaiveDate, Vec<f32>> = BTreeMap::new();
+        for ts in self.members.values() {
+            for tsp in ts.iter() {
+                grouped.entry(tsp.date).or_default().push(tsp.value);
+            }
+        }
+        grouped
+    }
+
+    pub fn mean(&self) -> TimeSeries {
+        self.group_by_valid_date()
+            .into_iter()
+            .map(|(date, vs)| TimeSeriesPoint {
+                date,
+                value: vs.iter().sum::<f32>() / vs.len() as f32,
+            })
+            .collect()
+    }
+
+    /// The spread of the members, i.e. their standard deviation per valid date.
+    pub fn spread(&self) -> TimeSeries {
+        self.group_by_valid_date()
+            .into_iter()
+            .map(|(date, vs)| {
+                let n = vs.len() as f32;
+                let mean = vs.iter().sum::<f32>() / n;
+                let variance = vs.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+                TimeSeriesPoint {
+                    date,
+                    value: variance.sqrt(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Converts the stored members of an ensemble forecast, none if there are no (convertible) members.
+pub fn convert_ensemble_responses<T: serde::de::DeserializeOwned>(
+    members: Vec<WeatherResponse>,
+    create_time_series: impl Fn(T) -> TimeSeries,
+) -> Option<EnsembleForecast> {
+    let as_of_date = members.first()?.as_of_date;
+    let members: BTreeMap<usize, TimeSeries> = members
+        .into_iter()
+        .flat_map(|response| {
+            let member = response.member.unwrap_or_default();
+            serde_json::from_value(response.forecast)
+                .map(|forecast_curve: T| (member, create_time_series(forecast_curve)))
+        })
+        .collect();
+    if members.is_empty() {
+        return None;
+    }
+    Some(EnsembleForecast {
+        as_of_date,
+        members,
+    })
+}
+
+pub fn print_ensemble_spread(ensemble: &EnsembleForecast) {
+    println!(
+        "Ensemble as of {} with {} members",
+        ensemble.as_of_date,
+        ensemble.members.len()
+    );
+    println!(
+        "{:>10} | {:>10} | {:>8} | {:>8}",
+        "days ahead", "valid date", "mean", "spread"
+    );
+    for (mean, spread) in ensemble.mean().iter().zip(ensemble.spread().iter()) {
+        println!(
+            "{:>10} | {:>10} | {:>8.2} | {:>8.2}",
+            lead_time(ensemble.as_of_date, mean.date).num_days(),
+            mean.date,
+            mean.value,
+            spread.value
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::data::models::{lead_time, HourlyForecastSet, HourlySeries, TimeSeriesPoint};
+use crate::data::weather::models::{HourlyCurve, WeatherResponse};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Converts the hourly forecasts into curves keyed by the hour they were fetched at (see `WeatherResponse::as_of_time`).
+pub fn convert_hourly_weather_responses<T: serde::de::DeserializeOwned>(
+    forecasts: Vec<WeatherResponse>,
+    create_time_series: impl Fn(T) -> HourlySeries,
+) -> HourlyForecastSet {
+    forecasts
+        .into_iter()
+        .flat_map(|fc| {
+            let as_of_time = fc.as_of_time();
+            serde_json::from_value(fc.forecast)
+                .map(|forecast_curve: T| (as_of_time, create_time_series(forecast_curve)))
+        })
+        .collect()
+}
+
+pub fn create_hourly_temperature_timeseries(forecast_curve: HourlyCurve) -> HourlySeries {
+    forecast_curve
+        .iter()
+        .map(|fc_pt| TimeSeriesPoint {
+            date: fc_pt.timestamp_local,
+            value: fc_pt.temp,
+        })
+        .collect()
+}
+
+/// The intra-day drift of the hourly forecasts, i.e. their difference to the latest forecast for the same hour.
+/// Contrary to the daily forecasts, the forecasts for the as-of hours themselves (the diagonal) are not available
+/// for all hours, as the runs are fetched a few times a day only.
+pub fn forecast_drift(forecasts: &HourlyForecastSet) -> HourlyForecastSet {
+    forecasts.relative_to(&forecasts.latest())
+}
+
+/// The mean absolute drift per lead time, excluding the latest forecasts themselves (which have no drift).
+pub fn mean_abs_drift(drift: &HourlyForecastSet) -> Vec<(Duration, f32)> {
+    let latest_runs: BTreeMap<NaiveDateTime, NaiveDateTime> = latest_runs(drift);
+    let mut grouped: BTreeMap<Duration, Vec<f32>> = BTreeMap::new();
+    for (as_of_time, ts) in drift.iter() {
+        for tsp in ts.iter() {
+            if latest_runs.get(&tsp.date) == Some(as_of_time) {
+                continue;
+            }
+            grouped
+                .entry(lead_time(*as_of_time, tsp.date))
+                .or_default()
+                .push(tsp.value.abs());
+        }
+    }
+    grouped
+        .into_iter()
+        .map(|(lead, vs)| (lead, vs.iter().sum::<f32>() / vs.len() as f32))
+        .collect()
+}
+
+/// The as-of time of the latest forecast per valid hour.
+fn latest_runs(forecasts: &HourlyForecastSet) -> BTreeMap<NaiveDateTime, NaiveDateTime> {
+    let mut latest = BTreeMap::new();
+    // the curves are ordered by their as-of time, i.e. later runs overwrite earlier ones
+    for (as_of_time, ts) in forecasts.iter() {
+        for tsp in ts.iter() {
+            latest.insert(tsp.date, *as_of_time);
+        }
+    }
+    latest
+}
+
+/// The drift laid out as grid for a heatmap: a row per lead time (in hours) and a column per valid hour.
+#[derive(Debug, Clone, Default)]
+pub struct DriftGrid {
+    pub valid_times: Vec<NaiveDateTime>,
+    pub lead_hours: Vec<i64>,
+    /// The drift per lead time (rows) and valid hour (columns), none if not forecasted
+    pub values: Vec<Vec<Option<f32>>>,
+}
+
+pub fn drift_grid(drift: &HourlyForecastSet) -> DriftGrid {
+    let valid_times: Vec<NaiveDateTime> = drift.group_by_valid_date().into_keys().collect();
+    let lead_hours: Vec<i64> = drift
+        .lead_times()
+        .iter()
+        .map(|lead| lead.num_hours())
+        .collect::<BTreeSet<i64>>()
+        .into_iter()
+        .collect();
+
+    let mut values = vec![vec![None; valid_times.len()]; lead_hours.len()];
+    for (as_of_time, ts) in drift.iter() {
+        for tsp in ts.iter() {
+            let lead = lead_time(*as_of_time, tsp.date).num_hours();
+            if let (Ok(row), Ok(col)) = (
+                lead_hours.binary_search(&lead),
+                valid_times.binary_search(&tsp.date),
+            ) {
+                values[row][col] = Some(tsp.value);
+            }
+        }
+    }
+    DriftGrid {
+        valid_times,
+        lead_hours,
+        values,
+    }
+}
+
+pub fn print_mean_abs_drift(mean_abs_drift: &[(Duration, f32)]) {
+    println!("{:>11} | {:>14}", "hours ahead", "mean abs drift");
+    for (lead, drift) in mean_abs_drift.iter() {
+        println!("{:>11} | {:>14.2}", lead.num_hours(), drift);
+    }
+}
@@ -1,9 +1,38 @@
+mod archive;
+mod baselines;
+mod bootstrap;
 mod conversions;
+mod ensemble;
+mod hourly;
 pub mod models;
+mod revisions;
+mod verification;
+#[allow(clippy::module_inception)]
 mod weather;
 
+pub use archive::ForecastArchive;
+pub use baselines::{baseline_forecasts, skill_scores, Baseline, Climatology};
+pub use bootstrap::{
+    bootstrap_intervals, forecast_errors, percentile_intervals, print_error_intervals,
+    BootstrapConfig, ConfidenceInterval, ErrorStatistic,
+};
 pub use conversions::{
-    convert_weather_responses, create_temperature_timeseries, percentile_timeseries, percentiles,
-    reference_timeseries,
+    convert_weather_responses, create_precipitation_probability_timeseries,
+    create_precipitation_timeseries, create_temperature_timeseries, percentile_timeseries,
+    percentiles, percentiles_relative_to,
+};
+pub use ensemble::{
+    convert_ensemble_responses, print_ensemble_spread, EnsembleApi, EnsembleForecast,
+};
+pub use hourly::{
+    convert_hourly_weather_responses, create_hourly_temperature_timeseries, drift_grid,
+    forecast_drift, mean_abs_drift, print_mean_abs_drift,
+};
+pub use revisions::{
+    most_volatile_days, print_volatility_ranking, revisions, volatility_by_lead_time,
+    volatility_by_valid_date, Volatility,
+};
+pub use verification::{
+    brier_scores, precipitation_occurrence, print_brier_scores, reliability_diagram, ReliabilityBin,
 };
-pub use weather::WeatherLocation;
+pub use weather::{Resolution, WeatherLocation, HOURLY_POINTS};
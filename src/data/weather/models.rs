@@ -1,20 +1,12 @@
-use chrono::Date;
 use chrono::DateTime;
+use chrono::FixedOffset;
 use chrono::NaiveDate;
 use chrono::NaiveDateTime;
-use chrono::Utc;
+use chrono::Timelike;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
 
-// Cannot impl the From trait due to orphan rule..
-fn from(date: &str) -> Date<Utc> {
-    let naive_date = NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
-    let naive_datetime: NaiveDateTime = naive_date.and_hms(0, 0, 0);
-    let datetime_utc = DateTime::<Utc>::from_utc(naive_datetime, Utc);
-    datetime_utc.date()
-}
-
 /*
   "moonrise_ts": 1658709007,
   "wind_cdir": "SW",
@@ -61,14 +53,58 @@ fn from(date: &str) -> Date<Utc> {
 */
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct WeatherResponse {
-    #[serde(rename = "asOfDate")]
-    pub as_of_date: String,
+    /// The date of the forecast in the timezone of the location
+    #[serde(rename = "asOfDate", with = "as_of_date_format")]
+    pub as_of_date: NaiveDate,
+    /// The time the forecast was fetched, not available for forecasts stored before it was recorded
+    #[serde(rename = "fetchedAt", default, skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<DateTime<FixedOffset>>,
+    /// Whether the forecast was backfilled from an archive of past forecast runs rather than fetched as of its date
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub backfilled: bool,
+    /// The provider of the forecast, weatherbit if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The member of an ensemble forecast, none for deterministic forecasts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member: Option<usize>,
     pub forecast: Value, // NOTE: store the full response data for further future plots
 }
 
 impl WeatherResponse {
-    pub fn date(&self) -> chrono::Date<Utc> {
-        from(&self.as_of_date.replace("UTC", ""))
+    pub fn date(&self) -> NaiveDate {
+        self.as_of_date
+    }
+
+    /// The (local) date-time of the hour the forecast was fetched, identifying the runs of hourly forecasts.
+    /// Forecasts without fetch time are considered as of midnight.
+    pub fn as_of_time(&self) -> NaiveDateTime {
+        self.fetched_at
+            .and_then(|fetched_at| {
+                let local = fetched_at.naive_local();
+                local.date().and_hms_opt(local.hour(), 0, 0)
+            })
+            .unwrap_or_else(|| self.as_of_date.and_hms_opt(0, 0, 0).unwrap())
+    }
+}
+
+pub mod as_of_date_format {
+    use chrono::NaiveDate;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub const FORMAT: &str = "%Y-%m-%d";
+    /// Forecasts stored before the migration to naive dates carry the timezone, e.g. '2022-07-25UTC'
+    pub const LEGACY_FORMAT: &str = "%Y-%m-%dUTC";
+
+    pub fn serialize<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&date.format(FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+        let date = String::deserialize(deserializer)?;
+        NaiveDate::parse_from_str(&date, FORMAT)
+            .or_else(|_| NaiveDate::parse_from_str(&date, LEGACY_FORMAT))
+            .map_err(serde::de::Error::custom)
     }
 }
 
@@ -77,12 +113,34 @@ pub type ForecastCurve = Vec<ForecastTemperaturePoint>;
 
 #[derive(Debug, Deserialize)]
 pub struct ForecastTemperaturePoint {
-    pub valid_date: String,
+    pub valid_date: NaiveDate,
     pub max_temp: f32,
 }
 
 impl ForecastTemperaturePoint {
-    pub fn date(&self) -> Date<Utc> {
-        from(&self.valid_date)
+    pub fn date(&self) -> NaiveDate {
+        self.valid_date
     }
 }
+
+pub type PrecipitationCurve = Vec<ForecastPrecipitationPoint>;
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastPrecipitationPoint {
+    pub valid_date: NaiveDate,
+    /// The probability of precipitation in percent, missing in some backfilled forecasts
+    pub pop: Option<f32>,
+    /// The accumulated precipitation in mm
+    pub precip: f32,
+}
+
+/// The hourly points of a weatherbit hourly forecast, e.g. `{"ts": 1658750400, "timestamp_local": "2022-07-25T14:00:00", "temp": 25.6, ..}`.
+pub type HourlyCurve = Vec<HourlyForecastPoint>;
+
+#[derive(Debug, Deserialize)]
+pub struct HourlyForecastPoint {
+    /// The date-time of the hour in the timezone of the location.
+    /// The hour repeated when the daylight saving time ends occurs twice, the series keep the latter one only.
+    pub timestamp_local: NaiveDateTime,
+    pub temp: f32,
+}
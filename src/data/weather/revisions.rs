@@ -0,0 +1,121 @@
+use crate::data::models::{lead_time, ForecastSet};
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+/// The change of the forecast for a valid date from one as-of date to the next available one.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub valid_date: NaiveDate,
+    /// The lead time of the newer forecast
+    pub lead_time: Duration,
+    pub change: f32,
+    /// Whether the revision reverses the direction of the previous revision for the same valid date
+    pub flip_flop: bool,
+}
+
+/// All revisions of the forecasts, ordered by valid date and as-of date.
+pub fn revisions(forecasts: &ForecastSet) -> Vec<Revision> {
+    let mut revisions = Vec::new();
+    for valid_date in forecasts.group_by_valid_date().keys() {
+        let by_as_of = forecasts.by_valid_date(valid_date);
+        let mut previous_change: Option<f32> = None;
+
+        for (older, newer) in by_as_of.iter().zip(by_as_of.iter().skip(1)) {
+            let change = newer.value - older.value;
+            // unchanged forecasts neither flip nor reset the direction
+            let flip_flop = match previous_change {
+                Some(previous) => change * previous < 0.0,
+                None => false,
+            };
+            if change != 0.0 {
+                previous_change = Some(change);
+            }
+            revisions.push(Revision {
+                valid_date: *valid_date,
+                lead_time: lead_time(newer.date, *valid_date),
+                change,
+                flip_flop,
+            });
+        }
+    }
+    revisions
+}
+
+/// The summary of several revisions, e.g. of all revisions of a valid date or lead time.
+#[derive(Debug, Clone, Default)]
+pub struct Volatility {
+    pub count: usize,
+    pub mean_abs_revision: f32,
+    pub max_abs_revision: f32,
+    pub flip_flops: usize,
+    /// The fraction of the revisions reversing the direction of the previous one
+    pub flip_flop_frequency: f32,
+}
+
+impl Volatility {
+    fn add(&mut self, revision: &Revision) {
+        let abs_change = revision.change.abs();
+        self.mean_abs_revision =
+            (self.mean_abs_revision * self.count as f32 + abs_change) / (self.count + 1) as f32;
+        self.max_abs_revision = self.max_abs_revision.max(abs_change);
+        self.count += 1;
+        if revision.flip_flop {
+            self.flip_flops += 1;
+        }
+        self.flip_flop_frequency = self.flip_flops as f32 / self.count as f32;
+    }
+}
+
+pub fn volatility_by_valid_date(revisions: &[Revision]) -> BTreeMap<NaiveDate, Volatility> {
+    let mut volatility: BTreeMap<NaiveDate, Volatility> = BTreeMap::new();
+    for revision in revisions.iter() {
+        volatility
+            .entry(revision.valid_date)
+            .or_default()
+            .add(revision);
+    }
+    volatility
+}
+
+pub fn volatility_by_lead_time(revisions: &[Revision]) -> BTreeMap<Duration, Volatility> {
+    let mut volatility: BTreeMap<Duration, Volatility> = BTreeMap::new();
+    for revision in revisions.iter() {
+        volatility
+            .entry(revision.lead_time)
+            .or_default()
+            .add(revision);
+    }
+    volatility
+}
+
+/// The valid dates with the largest mean absolute revision.
+pub fn most_volatile_days(
+    volatility: &BTreeMap<NaiveDate, Volatility>,
+    top_n: usize,
+) -> Vec<(NaiveDate, Volatility)> {
+    let mut ranked: Vec<(NaiveDate, Volatility)> = volatility
+        .iter()
+        .map(|(date, v)| (*date, v.clone()))
+        .collect();
+    ranked.sort_by(|(_, a), (_, b)| b.mean_abs_revision.total_cmp(&a.mean_abs_revision));
+    ranked.truncate(top_n);
+    ranked
+}
+
+pub fn print_volatility_ranking(ranking: &[(NaiveDate, Volatility)]) {
+    println!(
+        "{:>4} | {:>10} | {:>9} | {:>8} | {:>8} | {:>10}",
+        "rank", "valid date", "revisions", "mean abs", "max abs", "flip-flops"
+    );
+    for (idx, (valid_date, volatility)) in ranking.iter().enumerate() {
+        println!(
+            "{:>4} | {:>10} | {:>9} | {:>8.2} | {:>8.2} | {:>10}",
+            idx + 1,
+            valid_date,
+            volatility.count,
+            volatility.mean_abs_revision,
+            volatility.max_abs_revision,
+            volatility.flip_flops
+        );
+    }
+}
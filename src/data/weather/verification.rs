@@ -0,0 +1,194 @@
+use crate::data::models::{lead_time, ForecastSet, TimeSeries};
+use chrono::Duration;
+use std::collections::BTreeMap;
+
+/// The minimum precipitation in mm for a day to count as a day with precipitation.
+pub const PRECIPITATION_THRESHOLD_MM: f32 = 0.1;
+
+/// The number of probability bins (of width 10%) of the reliability diagram.
+const PROBABILITY_BINS: usize = 11;
+
+/// Whether it rained (1.0) or not (0.0) per day.
+/// NOTE: the observations are approximated by the precipitation forecasts for the as-of dates themselves (the diagonal).
+pub fn precipitation_occurrence(precipitation: &ForecastSet) -> TimeSeries {
+    precipitation.diagonal().map(|precip| {
+        if precip >= PRECIPITATION_THRESHOLD_MM {
+            1.0
+        } else {
+            0.0
+        }
+    })
+}
+
+/// The pairs of (forecast probability, observed occurrence) per lead time, excluding the as-of dates themselves.
+fn probability_pairs(
+    probabilities: &ForecastSet,
+    occurrence: &TimeSeries,
+) -> BTreeMap<Duration, Vec<(f32, f32)>> {
+    let mut pairs_by_d: BTreeMap<Duration, Vec<(f32, f32)>> = BTreeMap::new();
+    for (as_of_date, ts) in probabilities.iter() {
+        for tsp in ts.iter() {
+            let days_ahead = lead_time(*as_of_date, tsp.date);
+            if days_ahead <= Duration::zero() {
+                continue;
+            }
+            if let Some(observed) = occurrence.get(&tsp.date) {
+                pairs_by_d
+                    .entry(days_ahead)
+                    .or_default()
+                    .push((tsp.value, observed));
+            }
+        }
+    }
+    pairs_by_d
+}
+
+/// A bin of forecast probabilities with the observed frequency of the event.
+#[derive(Debug, Clone)]
+pub struct ReliabilityBin {
+    /// The mean forecast probability within the bin
+    pub forecast_probability: f32,
+    /// The relative frequency of the event, i.e. of days with precipitation
+    pub observed_frequency: f32,
+    pub count: usize,
+}
+
+/// Groups the pairs into bins of width 10% centered at 0%, 10%, ..., 100%, omitting empty bins.
+fn reliability_bins(pairs: &[(f32, f32)]) -> Vec<ReliabilityBin> {
+    let mut sums = [(0.0f32, 0.0f32, 0usize); PROBABILITY_BINS];
+    for (probability, observed) in pairs.iter() {
+        let bin = ((probability * 10.0).round() as usize).min(PROBABILITY_BINS - 1);
+        sums[bin].0 += probability;
+        sums[bin].1 += observed;
+        sums[bin].2 += 1;
+    }
+
+    sums.iter()
+        .filter(|(_, _, count)| *count > 0)
+        .map(|(probability_sum, observed_sum, count)| ReliabilityBin {
+            forecast_probability: probability_sum / *count as f32,
+            observed_frequency: observed_sum / *count as f32,
+            count: *count,
+        })
+        .collect()
+}
+
+/// The reliability diagram over all lead times (excluding the as-of dates themselves).
+/// The counts of the bins form the sharpness histogram.
+pub fn reliability_diagram(
+    probabilities: &ForecastSet,
+    occurrence: &TimeSeries,
+) -> Vec<ReliabilityBin> {
+    let pairs: Vec<(f32, f32)> = probability_pairs(probabilities, occurrence)
+        .into_values()
+        .flatten()
+        .collect();
+    reliability_bins(&pairs)
+}
+
+/// The Brier score of the probability forecasts with a given lead time and its (binned) Murphy decomposition
+/// `brier ≈ reliability - resolution + uncertainty`.
+#[derive(Debug, Clone)]
+pub struct BrierScore {
+    pub lead_time: Duration,
+    pub count: usize,
+    pub brier: f32,
+    pub reliability: f32,
+    pub resolution: f32,
+    pub uncertainty: f32,
+}
+
+pub fn brier_scores(probabilities: &ForecastSet, occurrence: &TimeSeries) -> Vec<BrierScore> {
+    probability_pairs(probabilities, occurrence)
+        .into_iter()
+        .map(|(days_ahead, pairs)| {
+            let n = pairs.len() as f32;
+            let brier = pairs.iter().map(|(p, o)| (p - o).powi(2)).sum::<f32>() / n;
+            let base_rate = pairs.iter().map(|(_, o)| o).sum::<f32>() / n;
+
+            let bins = reliability_bins(&pairs);
+            let reliability = bins
+                .iter()
+                .map(|b| b.count as f32 * (b.forecast_probability - b.observed_frequency).powi(2))
+                .sum::<f32>()
+                / n;
+            let resolution = bins
+                .iter()
+                .map(|b| b.count as f32 * (b.observed_frequency - base_rate).powi(2))
+                .sum::<f32>()
+                / n;
+
+            BrierScore {
+                lead_time: days_ahead,
+                count: pairs.len(),
+                brier,
+                reliability,
+                resolution,
+                uncertainty: base_rate * (1.0 - base_rate),
+            }
+        })
+        .collect()
+}
+
+pub fn print_brier_scores(scores: &[BrierScore]) {
+    println!(
+        "{:>10} | {:>5} | {:>6} | {:>11} | {:>10} | {:>11}",
+        "days ahead", "count", "brier", "reliability", "resolution", "uncertainty"
+    );
+    for score in scores.iter() {
+        println!(
+            "{:>10} | {:>5} | {:>6.3} | {:>11.3} | {:>10.3} | {:>11.3}",
+            score.lead_time.num_days(),
+            score.count,
+            score.brier,
+            score.reliability,
+            score.resolution,
+            score.uncertainty
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::models::TimeSeriesPoint;
+    use chrono::NaiveDate;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn series(points: &[(&str, f32)]) -> TimeSeries {
+        TimeSeries::new(
+            points
+                .iter()
+                .map(|(d, value)| TimeSeriesPoint {
+                    date: date(d),
+                    value: *value,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn brier_score_of_known_probabilities() {
+        let probabilities: ForecastSet = [
+            (date("2022-01-01"), series(&[("2022-01-02", 0.8)])),
+            (date("2022-01-02"), series(&[("2022-01-03", 0.3)])),
+        ]
+        .into_iter()
+        .collect();
+        let occurrence = series(&[("2022-01-02", 1.0), ("2022-01-03", 0.0)]);
+
+        let scores = brier_scores(&probabilities, &occurrence);
+        assert_eq!(scores.len(), 1);
+        let score = &scores[0];
+        assert_eq!(score.lead_time, Duration::days(1));
+        assert_eq!(score.count, 2);
+        // ((0.8 - 1)^2 + (0.3 - 0)^2) / 2
+        assert!((score.brier - 0.065).abs() < 1e-6);
+        assert!((score.reliability - 0.065).abs() < 1e-6);
+        assert!((score.resolution - 0.25).abs() < 1e-6);
+        assert!((score.uncertainty - 0.25).abs() < 1e-6);
+    }
+}
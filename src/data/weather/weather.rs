@@ -1,12 +1,38 @@
+use crate::data::http::HttpClient;
 use crate::data::weather::models::WeatherResponse;
-use chrono::Utc;
-use serde_json::Value;
-use std::error::Error as std_error;
+use crate::errors::AppError;
+use chrono::{DateTime, Offset, Utc};
+use chrono_tz::Tz;
 use std::result::Result;
 
+pub const WEATHERBIT_URL: &str = "https://api.weatherbit.io/v2.0/forecast/daily";
+pub const WEATHERBIT_HOURLY_URL: &str = "https://api.weatherbit.io/v2.0/forecast/hourly";
+/// The number of hourly points requested of a weatherbit hourly forecast.
+pub const HOURLY_POINTS: usize = 48;
+
+/// The resolution of the forecasts: daily points for the next 16 days or hourly points for the next 48 hours.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    #[default]
+    Daily,
+    Hourly,
+}
+
+impl Resolution {
+    pub fn url(&self) -> &'static str {
+        match self {
+            Resolution::Daily => WEATHERBIT_URL,
+            Resolution::Hourly => WEATHERBIT_HOURLY_URL,
+        }
+    }
+}
+
 pub struct WeatherLocation {
     pub country_code: String,
     pub zip: String,
+    /// The timezone of the location, defining the dates of the (daily) forecasts
+    pub timezone: Tz,
 }
 
 impl std::fmt::Display for WeatherLocation {
@@ -16,22 +42,46 @@ impl std::fmt::Display for WeatherLocation {
 }
 
 impl WeatherLocation {
-    pub fn new(country_code: String, zip: String) -> Self {
-        Self { country_code, zip }
+    pub fn new(country_code: String, zip: String, timezone: Tz) -> Self {
+        Self {
+            country_code,
+            zip,
+            timezone,
+        }
+    }
+
+    /// The current time at the location.
+    pub fn now(&self) -> DateTime<Tz> {
+        Utc::now().with_timezone(&self.timezone)
     }
 
-    /// Loads the most recent weather forecast from the API
-    pub fn load_weather_data(&self, api_key: &str) -> Result<WeatherResponse, Box<dyn std_error>> {
-        let url = format!(
-            "https://api.weatherbit.io/v2.0/forecast/daily?postal_code={}&country={}&key={}",
-            self.zip, self.country_code, api_key
-        );
-        let resp_json = reqwest::blocking::get(url)?.json::<Value>()?;
+    /// Loads the most recent weather forecast of the resolution from the API (see `WEATHERBIT_URL` resp. `WEATHERBIT_HOURLY_URL`)
+    pub fn load_weather_data(
+        &self,
+        client: &HttpClient,
+        url: &str,
+        api_key: &str,
+        as_of: &DateTime<Tz>,
+        resolution: Resolution,
+    ) -> Result<WeatherResponse, AppError> {
+        let mut query = vec![
+            ("postal_code", self.zip.clone()),
+            ("country", self.country_code.clone()),
+            ("key", api_key.to_string()),
+        ];
+        if resolution == Resolution::Hourly {
+            query.push(("hours", HOURLY_POINTS.to_string()));
+        }
+        let resp_json = client.get_json(url, &query, &[])?;
 
         // NOTE: we dump the whole response data for now, so that we can analyze further graphs later
         let response = WeatherResponse {
-            as_of_date: Utc::now().date().to_string(),
-            forecast: (&resp_json["data"]).clone(),
+            as_of_date: as_of.naive_local().date(),
+            fetched_at: Some(as_of.with_timezone(&as_of.offset().fix())),
+            backfilled: false,
+            source: None,
+            member: None,
+            forecast: resp_json["data"].clone(),
         };
 
         Ok(response)
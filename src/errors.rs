@@ -6,10 +6,26 @@ pub enum AppError {
     SetupEnvVar(#[from] std::env::VarError),
     #[error("Error in setup: {0}")]
     SetupConfig(#[from] config::ConfigError),
+    #[error("Error in setup: invalid timezone {0}")]
+    SetupTimezone(String),
+    #[error("Error in setup: invalid schedule {0}")]
+    SetupSchedule(String),
+    #[error("Error in setup: invalid dataset {0}")]
+    SetupDataset(String),
+    #[error("Data directory is locked by another run, remove the lock file {0:?} if no other run is active")]
+    Locked(std::path::PathBuf),
+    #[error("HTTP error: {0}")]
+    Http(String),
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+    #[error("Request error: {0}")]
+    Request(#[from] reqwest::Error),
     #[error("IO error: {0}")]
     IO(#[from] std::io::Error),
     #[error("Serialization error: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error("Data frame error: {0}")]
+    Polars(#[from] polars::prelude::PolarsError),
     #[error("generic error {0}")]
     Dynamic(#[from] Box<dyn std::error::Error>),
 }
@@ -6,28 +6,50 @@ extern crate serde;
 mod data;
 mod errors;
 mod plot;
+mod scheduler;
 
+use crate::data::covid::{
+    filter_countries, metric_series, per_capita, print_country_summary, seven_day_average,
+    CovidMetric, ROLLING_DAYS,
+};
+use crate::data::dataset::{
+    dataset_series, dataset_vintages, print_dataset_summary, DatasetConfig,
+};
 use crate::data::football::{
-    column_vec, convert_data_frame, filter_players, join_players, FootballSeasonResults,
+    aggregate_teams, column_values, convert_data_frame, filter_players, pivot_column,
+    pivot_players, player_names, player_profiles, print_leaderboard, rank_players,
+    rank_trajectories, team_curves, top_entries, write_leaderboard_csv, CareerAxis, FootballMetric,
+    FootballSeasonResults, PlayerFilter, RankingScope, StatisticsGrouping, TeamMetric,
 };
-use crate::data::models::{TimeSeries, TimeSeriesPoint};
+use crate::data::models::{ForecastSet, HourlyForecastSet};
 use crate::data::weather::{
-    convert_weather_responses, create_temperature_timeseries, models::WeatherResponse,
-    reference_timeseries,
+    baseline_forecasts, bootstrap_intervals, brier_scores, convert_ensemble_responses,
+    convert_hourly_weather_responses, convert_weather_responses,
+    create_hourly_temperature_timeseries, create_precipitation_probability_timeseries,
+    create_precipitation_timeseries, create_temperature_timeseries, forecast_drift,
+    forecast_errors, mean_abs_drift, models::WeatherResponse, most_volatile_days,
+    precipitation_occurrence, print_brier_scores, print_ensemble_spread, print_error_intervals,
+    print_mean_abs_drift, print_volatility_ranking, revisions, volatility_by_valid_date, Baseline,
+    BootstrapConfig, Climatology, EnsembleApi, ErrorStatistic, ForecastArchive, Resolution,
 };
 use crate::data::{
-    DataProvider, FootballLeague, FootballProvider, WeatherLocation, WeatherProvider,
+    Compression, CovidProvider, DataLock, DataProvider, DatasetProvider, FootballLeague,
+    FootballProvider, HttpClient, HttpConfig, WeatherLocation, WeatherProvider, DEFAULT_DATA_ROOT,
 };
 use crate::plot::AnimationType;
-use crate::plot::Plot;
-use chrono::Date;
-use chrono::Utc;
+use crate::plot::{plot_series, HourlyPlot, Plot, PlotLabels, RadarProfile};
+use crate::scheduler::{parse_schedule, run_daemon, ScheduledFetch};
+use chrono::{Duration, NaiveDate};
+use chrono_tz::Tz;
 use clap::{Parser, Subcommand};
 use dotenv::dotenv;
 use env_logger::Env;
 use errors::AppError;
-use log::info;
+use log::{debug, info, warn};
+use polars::prelude::{DataFrame, Float32Type};
 use std::collections::HashMap;
+use std::ops::Bound;
+use std::path::PathBuf;
 use std::result::Result;
 use std::sync::Arc;
 use std::thread;
@@ -37,19 +59,80 @@ use std::thread;
 // - migrate to public "fun with plots / graphs"
 // - add Readme with gifs and chart
 // - add more graphs like pressure
-// - add corona data / different data source
 // - add (mongo) DB if data gets bigger?
 // - github actions
 
 const ENV_API_KEY: &str = "API_KEY";
+const ENV_FOOTBALL_API_KEY: &str = "FOOTBALL_API_KEY";
+const ENV_DATA_ROOT: &str = "DATA_ROOT";
+const ENV_PLOT_ROOT: &str = "PLOT_ROOT";
 const LOG_LEVEL: &str = "LOG_LEVEL";
+const DEFAULT_PLOT_ROOT: &str = "plots";
+/// Daily at 6am in the timezone of the location
+const DEFAULT_WEATHER_SCHEDULE: &str = "0 0 6 * * *";
 
 // TODO: structure into weather and football
 #[derive(serde::Deserialize, Debug)]
 struct Settings {
     pub country_code: String,
     pub zip: String,
-    pub football_country: String,
+    pub timezone: String,
+    /// The (cron) schedule of the daemon fetching the forecasts, in the timezone of the location
+    #[serde(default = "default_weather_schedule")]
+    pub weather_schedule: String,
+    /// Further locations fetched by the daemon
+    #[serde(default)]
+    pub weather_locations: Vec<WeatherLocationSettings>,
+    #[serde(default)]
+    pub football_leagues: Vec<FootballLeague>,
+    /// The legacy setting of a single league by its country, superseded by `football_leagues`
+    pub football_country: Option<String>,
+    /// Tabular datasets loaded from CSV or JSON files resp. urls
+    #[serde(default)]
+    pub datasets: Vec<DatasetConfig>,
+    /// Overrides the url of the weather API, e.g. with a local stub server
+    pub weather_api_url: Option<String>,
+    /// Overrides the url of the football API, e.g. with a local stub server
+    pub football_api_url: Option<String>,
+    /// Overrides the url of the archive of past forecast runs used for backfilling
+    pub weather_archive_url: Option<String>,
+    /// Overrides the url of the ensemble forecasts
+    pub ensemble_api_url: Option<String>,
+    /// Overrides the url of the geocoding API of the archive and the ensemble forecasts
+    pub geocoding_url: Option<String>,
+    /// The compression of newly saved data files, none by default
+    #[serde(default)]
+    pub data_compression: Compression,
+    /// The root directory of the data, relative to the working directory unless absolute
+    #[serde(default = "default_data_root")]
+    pub data_root: PathBuf,
+    /// The root directory of the plots saved to files, e.g. the animations
+    #[serde(default = "default_plot_root")]
+    pub plot_root: PathBuf,
+}
+
+fn default_data_root() -> PathBuf {
+    PathBuf::from(DEFAULT_DATA_ROOT)
+}
+
+fn default_plot_root() -> PathBuf {
+    PathBuf::from(DEFAULT_PLOT_ROOT)
+}
+
+fn default_weather_schedule() -> String {
+    DEFAULT_WEATHER_SCHEDULE.to_string()
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct WeatherLocationSettings {
+    pub country_code: String,
+    pub zip: String,
+    pub timezone: String,
+    /// The schedule of the location, the `weather_schedule` if not set
+    pub schedule: Option<String>,
+    /// Fetch the daily (default) or hourly forecasts of the location
+    #[serde(default)]
+    pub resolution: Resolution,
 }
 
 impl Settings {
@@ -57,118 +140,875 @@ impl Settings {
         let config = config::Config::builder()
             .add_source(config::File::from(std::path::Path::new("config.toml")))
             .build()?;
-        let settings = config.try_deserialize::<Settings>()?;
+        let mut settings = config.try_deserialize::<Settings>()?;
+        settings.migrate_football_country();
         Ok(settings)
     }
+
+    /// Replaces the legacy `football_country` by its top league, unless `football_leagues` are configured.
+    fn migrate_football_country(&mut self) {
+        let country = match &self.football_country {
+            Some(country) if self.football_leagues.is_empty() => country,
+            _ => return,
+        };
+        match FootballLeague::top_league(country) {
+            Some(league) => {
+                warn!(
+                    "The setting `football_country` is deprecated, using the league {} (id {}); configure `football_leagues` instead",
+                    league.name, league.id
+                );
+                self.football_leagues.push(league);
+            }
+            None => warn!(
+                "The setting `football_country` is deprecated and no league is known for {}, configure `football_leagues` instead",
+                country
+            ),
+        }
+    }
+
+    /// Overrides the root directories by the environment and the command line arguments (in that order).
+    pub fn with_overrides(mut self, args: &CliArgs) -> Self {
+        if let Ok(data_root) = std::env::var(ENV_DATA_ROOT) {
+            self.data_root = PathBuf::from(data_root);
+        }
+        if let Ok(plot_root) = std::env::var(ENV_PLOT_ROOT) {
+            self.plot_root = PathBuf::from(plot_root);
+        }
+        if let Some(data_root) = &args.data_root {
+            self.data_root = data_root.clone();
+        }
+        if let Some(plot_root) = &args.plot_root {
+            self.plot_root = plot_root.clone();
+        }
+        self
+    }
 }
 
 #[derive(Subcommand, Debug)]
 enum JobArgument {
-    Weather,
-    Football,
+    /// Create the data and plot directories of the configured locations and leagues
+    Init,
+    Weather(WeatherArgs),
+    Football(FootballArgs),
+    /// Load and plot a dataset configured as `[[datasets]]`
+    Dataset(DatasetArgs),
+    /// Compare the COVID-19 figures of countries, imported from an OWID-style CSV
+    Covid(CovidArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct WeatherArgs {
+    #[clap(subcommand)]
+    job: Option<WeatherJob>,
+    /// The baselines to compute the skill scores of the forecasts against
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values = &["persistence", "climatology", "previous_forecast"]
+    )]
+    baselines: Vec<Baseline>,
+    /// A CSV file (columns 'date' and 'value') with historic observations to extend the climatology
+    #[clap(long)]
+    climatology_csv: Option<PathBuf>,
+    /// The number of bootstrap resamples for the confidence intervals of the error statistics
+    #[clap(long, default_value_t = 500)]
+    bootstrap_resamples: usize,
+    /// The number of consecutive as-of dates resampled together, to respect the autocorrelation of the errors
+    #[clap(long, default_value_t = 3)]
+    block_length: usize,
+    /// The confidence level of the intervals, between 0 and 1 (exclusive)
+    #[clap(long, value_parser = parse_confidence, default_value_t = 0.9)]
+    confidence: f32,
+    /// The number of most volatile forecast days to show, i.e. with the largest forecast revisions
+    #[clap(long, default_value_t = 10)]
+    top_volatile: usize,
+    /// Analyse the daily forecasts or the hourly ones, e.g. for the intra-day drift of the forecasts
+    #[clap(long, value_enum, default_value = "daily")]
+    resolution: Resolution,
+    /// Exclude the forecasts backfilled from the archive, i.e. analyse the fetched forecasts only
+    #[clap(long)]
+    exclude_backfilled: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum WeatherJob {
+    /// Keep running and fetch the forecasts of all configured locations on their schedules
+    Daemon,
+    /// Backfill the forecasts of past as-of dates from an archive of forecast runs (Open-Meteo)
+    Backfill(BackfillArgs),
+    /// Fetch the ensemble forecast (Open-Meteo) and plot its spread next to the historical forecast errors
+    Ensemble,
+}
+
+#[derive(clap::Args, Debug)]
+struct BackfillArgs {
+    /// The first as-of date to backfill, e.g. 2022-06-01
+    #[clap(long)]
+    from: NaiveDate,
+    /// The last as-of date to backfill, yesterday if not set
+    #[clap(long)]
+    to: Option<NaiveDate>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CovidArgs {
+    #[clap(subcommand)]
+    job: Option<CovidJob>,
+    /// The iso codes of the countries to compare
+    #[clap(long, value_delimiter = ',', default_values = &["CHE", "DEU", "ITA", "FRA"])]
+    countries: Vec<String>,
+    /// The metrics to compare the countries by, the daily ones as 7-day average
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values = &["new_cases", "new_deaths"]
+    )]
+    metrics: Vec<CovidMetric>,
+    /// Compare the absolute figures instead of the rates per 100'000 inhabitants
+    #[clap(long)]
+    absolute: bool,
+    /// The first date to include
+    #[clap(long)]
+    from: Option<NaiveDate>,
+    /// The last date to include
+    #[clap(long)]
+    to: Option<NaiveDate>,
+}
+
+#[derive(Subcommand, Debug)]
+enum CovidJob {
+    /// Import an OWID-style CSV (a row per country and date) into the data directory
+    Import(ImportArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportArgs {
+    /// The CSV file, e.g. downloaded from https://covid.ourworldindata.org/data/owid-covid-data.csv
+    file: PathBuf,
+}
+
+#[derive(clap::Args, Debug)]
+struct DatasetArgs {
+    /// The name of the configured dataset
+    name: String,
+    /// The plots of the value columns, all but the chart require the as-of dates of the values
+    #[clap(long, value_enum, value_delimiter = ',', default_values = &["chart"])]
+    plots: Vec<DatasetPlot>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum DatasetPlot {
+    /// The series resp. the curves of the vintages
+    Chart,
+    /// The percentile levels of the differences to the values as of their date per lead time
+    Levels,
+    #[clap(name = "level_ts")]
+    LevelTs,
+    /// The size of the revisions between successive as-of dates
+    Revisions,
+    Animation,
+}
+
+#[derive(clap::Args, Debug)]
+struct FootballArgs {
+    #[clap(subcommand)]
+    job: Option<FootballJob>,
+    /// The ids of the configured leagues to load, all configured leagues if not set
+    #[clap(long, value_delimiter = ',')]
+    leagues: Vec<i32>,
+    /// Align the player careers by season or by the age of the players
+    #[clap(long, value_enum, default_value = "season")]
+    align_by: CareerAxis,
+    /// The metrics to compare the players by
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values = &["goals_per_90", "passes_per_90"]
+    )]
+    metrics: Vec<FootballMetric>,
+    /// Aggregate the statistics per player or keep them split by team and league
+    #[clap(long, value_enum, default_value = "player")]
+    group_by: StatisticsGrouping,
+    /// The ids of the players to compare, e.g. L.Messi: 154, C.Ronaldo: 874
+    #[clap(long, value_delimiter = ',', default_values = &["154", "874"])]
+    players: Vec<i32>,
+    /// The first season to include
+    #[clap(long)]
+    from_season: Option<i32>,
+    /// The last season to include
+    #[clap(long)]
+    to_season: Option<i32>,
+}
+
+#[derive(Subcommand, Debug)]
+enum FootballJob {
+    /// Fetch the player statistics of the seasons from the API, unless already stored
+    Fetch(FetchArgs),
+    /// Rank the players by a metric per season or over all seasons
+    Leaderboard(LeaderboardArgs),
+    /// Aggregate the statistics per team and compare the teams over the seasons
+    Teams(TeamsArgs),
+    /// Compare the profiles of the players in a radar chart, normalized to percentiles of the league
+    Radar(RadarArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct FetchArgs {
+    /// The seasons to fetch, e.g. 2021,2022
+    #[clap(long, value_delimiter = ',', required = true)]
+    seasons: Vec<i32>,
+}
+
+#[derive(clap::Args, Debug)]
+struct RadarArgs {
+    /// The season to compare, the career of the players if not set
+    #[clap(long)]
+    season: Option<i32>,
+    /// The metrics forming the axes of the radar chart
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values = &["goals_per_90", "passes_per_90", "duel_win_rate", "pass_accuracy", "discipline_per_90"]
+    )]
+    axes: Vec<FootballMetric>,
+    /// The minimum number of minutes played of the players the percentiles are computed against
+    #[clap(long, default_value_t = 900)]
+    min_minutes: i32,
+}
+
+#[derive(clap::Args, Debug)]
+struct TeamsArgs {
+    /// The team metric to compare
+    #[clap(long, value_enum, default_value = "team_goals")]
+    metric: TeamMetric,
+    /// The ids of the teams to compare, e.g. Barcelona: 529, Real Madrid: 541; all teams if not set
+    #[clap(long, value_delimiter = ',')]
+    teams: Vec<i32>,
+}
+
+#[derive(clap::Args, Debug)]
+struct LeaderboardArgs {
+    /// The metric to rank the players by
+    #[clap(long, value_enum, default_value = "goals_per_90")]
+    metric: FootballMetric,
+    /// The minimum number of minutes played to be ranked
+    #[clap(long, default_value_t = 900)]
+    min_minutes: i32,
+    /// The number of top players to show
+    #[clap(long, default_value_t = 10)]
+    top: usize,
+    /// Rank the players within each season or over all seasons
+    #[clap(long, value_enum, default_value = "season")]
+    scope: RankingScope,
+    /// Write the leaderboard to the CSV file
+    #[clap(long)]
+    csv: Option<PathBuf>,
 }
 
 /// CLI to run the different data jobs.
 /// Examples:
+/// - cargo r init: create the data and plot directories
+/// - cargo r -- --data-root /var/lib/wiggler weather: use another data directory
 /// - cargo r weather: load latest data, plot graphs and animations
+/// - cargo r weather daemon: fetch the forecasts daily on the configured schedule until stopped
+/// - cargo r weather backfill --from 2022-06-01: backfill the forecasts since the date from the archive
+/// - cargo r weather ensemble: fetch the ensemble forecast and plot its spread against the forecast errors
+/// - cargo r weather --resolution hourly: fetch the hourly forecast and plot the intra-day drift of the forecasts
+/// - cargo r weather --baselines persistence --climatology-csv zurich.csv: compare the forecasts against the baselines
+/// - cargo r dataset zurich_observations: load the configured dataset and chart its value columns
+/// - cargo r dataset gdp_vintages --plots chart,revisions: plot the revisions of a dataset with as-of dates
+/// - cargo r covid import owid-covid-data.csv: import the COVID-19 figures of Our World in Data
+/// - cargo r covid --countries CHE,DEU,ITA --metrics new_cases: compare the 7-day average of the cases per 100'000 inhabitants
 /// - cargo r football: load data, plot graphs
+/// - cargo r football --leagues 140 fetch --seasons 2021,2022: fetch the statistics of the seasons from the API
+/// - cargo r football --align-by age: compare the players by age instead of season
+/// - cargo r football --metrics goals_per_90,duel_win_rate: compare the players by the given metrics
+/// - cargo r football --players 154,874 --from-season 2012 --to-season 2016: compare selected players and seasons
+/// - cargo r football --leagues 140,135: load the spanish and italian league only
+/// - cargo r football leaderboard --metric goals_per_90 --top 5: rank the players per season
+/// - cargo r football teams --metric top_scorer_share --teams 529,541: compare the teams over the seasons
+/// - cargo r football radar --season 2015: compare the profiles of the players in a radar chart
 #[derive(clap::Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct CliArgs {
     #[clap(subcommand)]
     data_source: JobArgument,
+    /// The root directory of the data, overrides the `DATA_ROOT` environment variable and the `data_root` setting
+    #[clap(long, global = true)]
+    data_root: Option<PathBuf>,
+    /// The root directory of the plots, overrides the `PLOT_ROOT` environment variable and the `plot_root` setting
+    #[clap(long, global = true)]
+    plot_root: Option<PathBuf>,
+}
+
+fn football_provider(
+    settings: &Settings,
+    api_key: String,
+    league: &FootballLeague,
+    client: HttpClient,
+) -> FootballProvider {
+    let provider = FootballProvider::new(api_key, league.clone(), client)
+        .with_data_root(settings.data_root.clone())
+        .with_compression(settings.data_compression);
+    if let Some(legacy_path) = provider.legacy_data_path() {
+        warn!(
+            "Using the legacy data directory {:?} for the league {}, rename it to '{}'",
+            legacy_path, league.name, league
+        );
+    }
+    match &settings.football_api_url {
+        Some(url) => provider.with_url(url.clone()),
+        None => provider,
+    }
+}
+
+fn run_football_fetch(
+    settings: &Settings,
+    args: &FootballArgs,
+    fetch_args: &FetchArgs,
+) -> Result<(), AppError> {
+    let api_key = std::env::var(ENV_FOOTBALL_API_KEY)?;
+    let client = HttpClient::new(HttpConfig::default())?;
+    let _lock = DataLock::acquire(&settings.data_root)?;
+
+    for league in settings.football_leagues.iter() {
+        if !args.leagues.is_empty() && !args.leagues.contains(&league.id) {
+            continue;
+        }
+        let data_provider = football_provider(settings, api_key.clone(), league, client.clone());
+        for season in fetch_args.seasons.iter() {
+            data_provider.update_data(*season)?;
+        }
+    }
+    Ok(())
 }
 
-fn run_football(settings: &Settings) -> Result<(), AppError> {
-    let league = FootballLeague {
-        country: settings.football_country.to_lowercase(),
+fn run_football(settings: &Settings, args: &FootballArgs) -> Result<(), AppError> {
+    if let Some(FootballJob::Fetch(fetch_args)) = &args.job {
+        return run_football_fetch(settings, args, fetch_args);
+    }
+
+    let client = HttpClient::new(HttpConfig::default())?;
+    let mut results_by_league = Vec::new();
+    for league in settings.football_leagues.iter() {
+        if !args.leagues.is_empty() && !args.leagues.contains(&league.id) {
+            continue;
+        }
+        // the stored data is loaded only, hence no api key is required
+        let data_provider =
+            football_provider(settings, "NOT_SET".to_string(), league, client.clone());
+        let results_by_season: Vec<FootballSeasonResults> = data_provider.load_timeseries_data()?;
+        info!(
+            "loaded {} seasons of league {} ({})",
+            results_by_season.len(),
+            league.name,
+            league.country
+        );
+        results_by_league.push((league.clone(), results_by_season));
+    }
+    // the team aggregation requires the statistics split by team
+    let grouping = match &args.job {
+        Some(FootballJob::Teams(_)) => StatisticsGrouping::Team,
+        _ => args.group_by,
+    };
+    let season_resuls_df = convert_data_frame(results_by_league, grouping)?;
+
+    match &args.job {
+        None => run_player_comparison(&season_resuls_df, args),
+        Some(FootballJob::Leaderboard(leaderboard_args)) => {
+            run_leaderboard(&season_resuls_df, args, leaderboard_args)
+        }
+        Some(FootballJob::Teams(teams_args)) => run_teams(&season_resuls_df, args, teams_args),
+        Some(FootballJob::Radar(radar_args)) => run_radar(&season_resuls_df, args, radar_args),
+        Some(FootballJob::Fetch(_)) => Ok(()),
+    }
+}
+
+fn run_radar(
+    season_resuls_df: &DataFrame,
+    args: &FootballArgs,
+    radar_args: &RadarArgs,
+) -> Result<(), AppError> {
+    let filter = PlayerFilter {
+        player_ids: Vec::new(),
+        from_season: args.from_season,
+        to_season: args.to_season,
     };
-    let data_provider = FootballProvider::new("NOT_SET".to_string(), league);
-    let results_by_season: Vec<FootballSeasonResults> = data_provider.load_timeseries_data()?;
-    let season_resuls_df = convert_data_frame(results_by_season);
+    let seasons_df = filter_players(season_resuls_df, &filter)?;
+
+    let profiles = player_profiles(
+        &seasons_df,
+        &args.players,
+        &radar_args.axes,
+        radar_args.season,
+        radar_args.min_minutes,
+    )?;
+    let profiles: Vec<RadarProfile> = profiles
+        .into_iter()
+        .map(|profile| {
+            let percentiles = profile
+                .percentiles
+                .into_iter()
+                .map(|(metric, percentile)| (metric.name().to_string(), percentile))
+                .collect();
+            (profile.player_name, percentiles)
+        })
+        .collect();
 
-    // # SPANISH ligue: id 140
-    // # L.Messi: id 154
-    // # C.Ronaldo: id 874
-    let messi = filter_players(&season_resuls_df, 154);
-    dbg!(&messi);
-    let ronaldo = filter_players(&season_resuls_df, 874);
-    dbg!(&ronaldo);
+    let title = match radar_args.season {
+        Some(season) => format!("Player profiles {} (percentiles of the league)", season),
+        None => "Player career profiles (percentiles of the league)".to_string(),
+    };
+    crate::plot::plot_radar_chart(&title, &profiles);
 
-    let common_stats = join_players(messi, &ronaldo);
-    dbg!(&common_stats);
+    Ok(())
+}
 
-    fn filter_map(v: Vec<Option<f32>>, factor: f32) -> Vec<f32> {
-        v.into_iter()
-            .map(|x| x.map(|z| z * factor).unwrap_or_else(|| 0.0))
-            .collect()
+fn run_teams(
+    season_resuls_df: &DataFrame,
+    args: &FootballArgs,
+    teams_args: &TeamsArgs,
+) -> Result<(), AppError> {
+    let filter = PlayerFilter {
+        player_ids: Vec::new(),
+        from_season: args.from_season,
+        to_season: args.to_season,
+    };
+    let seasons_df = filter_players(season_resuls_df, &filter)?;
+
+    let teams_df = aggregate_teams(&seasons_df)?;
+    debug!("{:?}", teams_df);
+
+    let curves = team_curves(&teams_df, teams_args.metric, &teams_args.teams)?;
+    crate::plot::plot_xy_curves(
+        "Team metric curves",
+        "season",
+        &teams_args.metric.to_string(),
+        &curves,
+    );
+
+    Ok(())
+}
+
+fn run_leaderboard(
+    season_resuls_df: &DataFrame,
+    args: &FootballArgs,
+    leaderboard_args: &LeaderboardArgs,
+) -> Result<(), AppError> {
+    let filter = PlayerFilter {
+        player_ids: Vec::new(),
+        from_season: args.from_season,
+        to_season: args.to_season,
+    };
+    let seasons_df = filter_players(season_resuls_df, &filter)?;
+
+    let metric = leaderboard_args.metric;
+    let ranking = rank_players(
+        &seasons_df,
+        metric,
+        leaderboard_args.min_minutes,
+        leaderboard_args.scope,
+    )?;
+    let top_ranking = top_entries(&ranking, leaderboard_args.top);
+    print_leaderboard(metric, &top_ranking);
+
+    if let Some(csv_path) = &leaderboard_args.csv {
+        write_leaderboard_csv(metric, &top_ranking, csv_path)?;
+        info!("Saved leaderboard to {:?}", csv_path);
+    }
+
+    if let RankingScope::Season = leaderboard_args.scope {
+        let trajectories = rank_trajectories(&ranking, leaderboard_args.top);
+        crate::plot::plot_bump_chart(
+            &format!("Top {} players by {}", leaderboard_args.top, metric),
+            &trajectories,
+        );
+    }
+
+    Ok(())
+}
+
+fn run_player_comparison(
+    season_resuls_df: &DataFrame,
+    args: &FootballArgs,
+) -> Result<(), AppError> {
+    let filter = PlayerFilter {
+        player_ids: args.players.clone(),
+        from_season: args.from_season,
+        to_season: args.to_season,
+    };
+    let players_df = filter_players(season_resuls_df, &filter)?;
+    debug!("{:?}", players_df);
+    let names_by_id = player_names(&players_df)?;
+
+    let metric_names: Vec<&str> = args.metrics.iter().map(|m| m.name()).collect();
+    let common_stats = pivot_players(&players_df, args.align_by, &args.players, &metric_names)?;
+    debug!("{:?}", common_stats);
+
+    fn filter_map(v: Vec<Option<f32>>) -> Vec<f32> {
+        v.into_iter().map(|x| x.unwrap_or(0.0)).collect()
     }
 
     //TODO: should rather have curvees like timeseries each with season and values
     let mut metrics = HashMap::new();
-    metrics.insert(
-        "Messi.GPM",
-        filter_map(column_vec(&common_stats, "goals_per_minute"), 100.0),
+    for metric in args.metrics.iter() {
+        info!("{}: {}", metric, metric.formula());
+        for player_id in args.players.iter() {
+            let player_name = names_by_id
+                .get(player_id)
+                .cloned()
+                .unwrap_or_else(|| player_id.to_string());
+            let values = column_values::<Float32Type>(
+                &common_stats,
+                &pivot_column(metric.name(), *player_id),
+            )?;
+            metrics.insert(format!("{}.{}", player_name, metric), filter_map(values));
+        }
+    }
+
+    let axis_name = args.align_by.column_name();
+    let axis_values = filter_map(column_values::<Float32Type>(&common_stats, axis_name)?);
+
+    crate::plot::plot_metric_curves(&axis_values, axis_name, &metrics);
+
+    Ok(())
+}
+
+fn parse_timezone(timezone: &str) -> Result<Tz, AppError> {
+    timezone
+        .parse()
+        .map_err(|e| AppError::SetupTimezone(format!("{} ({})", timezone, e)))
+}
+
+/// Parses a confidence level, which is a fraction rather than a percentage.
+fn parse_confidence(confidence: &str) -> Result<f32, String> {
+    let confidence: f32 = confidence
+        .parse()
+        .map_err(|e| format!("{} ({})", confidence, e))?;
+    if confidence > 0.0 && confidence < 1.0 {
+        Ok(confidence)
+    } else {
+        Err(format!(
+            "{} is not between 0 and 1, e.g. 0.9 for 90%",
+            confidence
+        ))
+    }
+}
+
+fn weather_provider(
+    settings: &Settings,
+    api_key: String,
+    location: WeatherLocation,
+    client: HttpClient,
+) -> WeatherProvider {
+    let provider = WeatherProvider::new(api_key, location, client)
+        .with_data_root(settings.data_root.clone())
+        .with_compression(settings.data_compression);
+    match &settings.weather_api_url {
+        Some(url) => provider.with_url(url.clone()),
+        None => provider,
+    }
+}
+
+/// The main and the further configured weather locations along with their schedules and resolutions.
+fn weather_locations(
+    settings: &Settings,
+) -> Result<Vec<(WeatherLocation, &String, Resolution)>, AppError> {
+    let mut locations = vec![(
+        WeatherLocation::new(
+            settings.country_code.clone(),
+            settings.zip.clone(),
+            parse_timezone(&settings.timezone)?,
+        ),
+        &settings.weather_schedule,
+        Resolution::Daily,
+    )];
+    for location in settings.weather_locations.iter() {
+        locations.push((
+            WeatherLocation::new(
+                location.country_code.clone(),
+                location.zip.clone(),
+                parse_timezone(&location.timezone)?,
+            ),
+            location
+                .schedule
+                .as_ref()
+                .unwrap_or(&settings.weather_schedule),
+            location.resolution,
+        ));
+    }
+    Ok(locations)
+}
+
+fn run_weather_daemon(settings: &Settings) -> Result<(), AppError> {
+    let api_key = std::env::var(ENV_API_KEY)?;
+    let client = HttpClient::new(HttpConfig::default())?;
+
+    let mut fetches = Vec::new();
+    for (location, schedule, resolution) in weather_locations(settings)? {
+        fetches.push(ScheduledFetch::new(
+            weather_provider(settings, api_key.clone(), location, client.clone())
+                .with_resolution(resolution),
+            parse_schedule(schedule)?,
+        ));
+    }
+
+    info!("Starting the daemon for {} location(s)", fetches.len());
+    run_daemon(&settings.data_root, fetches)
+}
+
+fn run_weather_backfill(settings: &Settings, backfill_args: &BackfillArgs) -> Result<(), AppError> {
+    let client = HttpClient::new(HttpConfig::default())?;
+    let mut archive = ForecastArchive::default();
+    if let Some(url) = &settings.weather_archive_url {
+        archive.url = url.clone();
+    }
+    if let Some(url) = &settings.geocoding_url {
+        archive.geocoding_url = url.clone();
+    }
+
+    let weather_location = WeatherLocation::new(
+        settings.country_code.clone(),
+        settings.zip.clone(),
+        parse_timezone(&settings.timezone)?,
     );
-    metrics.insert(
-        "Ronaldo.GPM",
-        filter_map(column_vec(&common_stats, "goals_per_minute.other"), 100.0),
+    let to = match backfill_args.to {
+        Some(to) => to,
+        None => weather_location.now().naive_local().date() - Duration::days(1),
+    };
+    // the archive requires no API key
+    let data_provider = weather_provider(settings, "NOT_SET".to_string(), weather_location, client);
+
+    let _lock = DataLock::acquire(&settings.data_root)?;
+    let backfilled = data_provider.backfill_data(&archive, backfill_args.from, to)?;
+    info!(
+        "Backfilled {} forecasts as of {} to {}",
+        backfilled, backfill_args.from, to
     );
-    metrics.insert(
-        "Messi.PPM",
-        filter_map(column_vec(&common_stats, "passes_per_minute"), 1.0),
+    Ok(())
+}
+
+/// Fetches the ensemble forecast of the main location and plots its members and spread,
+/// compared to the error levels of the stored daily forecasts.
+fn run_weather_ensemble(settings: &Settings) -> Result<(), AppError> {
+    let client = HttpClient::new(HttpConfig::default())?;
+    let mut api = EnsembleApi::default();
+    if let Some(url) = &settings.ensemble_api_url {
+        api.url = url.clone();
+    }
+    if let Some(url) = &settings.geocoding_url {
+        api.geocoding_url = url.clone();
+    }
+
+    let weather_location = WeatherLocation::new(
+        settings.country_code.clone(),
+        settings.zip.clone(),
+        parse_timezone(&settings.timezone)?,
     );
-    metrics.insert(
-        "Ronaldo.PPM",
-        filter_map(column_vec(&common_stats, "passes_per_minute.other"), 1.0),
+    let now = weather_location.now();
+    // the ensemble API requires no API key, the stored daily forecasts are loaded only
+    let data_provider = weather_provider(settings, "NOT_SET".to_string(), weather_location, client);
+
+    info!("Check updates for ensemble forcast data");
+    {
+        let _lock = DataLock::acquire(&settings.data_root)?;
+        data_provider.update_ensemble_data(&api, &now)?;
+    }
+
+    let as_of_date = data_provider
+        .latest_ensemble_as_of_date()
+        .unwrap_or_else(|| now.naive_local().date());
+    let members = data_provider.load_ensemble_data(&as_of_date)?;
+    info!("loaded {} ensemble members", members.len());
+    let ensemble = match convert_ensemble_responses(members, create_temperature_timeseries) {
+        Some(ensemble) => ensemble,
+        None => {
+            info!("No ensemble forecast as of {}", as_of_date);
+            return Ok(());
+        }
+    };
+    print_ensemble_spread(&ensemble);
+
+    let forecasts: Vec<WeatherResponse> = data_provider.load_timeseries_data()?;
+    let temperature_forecasts: ForecastSet =
+        convert_weather_responses(forecasts, create_temperature_timeseries);
+
+    info!("Creating plots");
+    Plot::Ensemble(&ensemble).plot(&temperature_forecasts)
+}
+
+/// Fetches the hourly forecast of the main location and analyses the drift of the forecasts for the same hour.
+fn run_weather_hourly(settings: &Settings) -> Result<(), AppError> {
+    let api_key = std::env::var(ENV_API_KEY)?;
+
+    let weather_location = WeatherLocation::new(
+        settings.country_code.clone(),
+        settings.zip.clone(),
+        parse_timezone(&settings.timezone)?,
     );
+    let now = weather_location.now();
+    let client = HttpClient::new(HttpConfig::default())?;
+    let data_provider = weather_provider(settings, api_key, weather_location, client)
+        .with_resolution(Resolution::Hourly);
+
+    info!("Check updates for hourly forcast data");
+    {
+        let _lock = DataLock::acquire(&settings.data_root)?;
+        data_provider.update_data(&now)?;
+    }
+
+    let forecasts: Vec<WeatherResponse> = data_provider.load_timeseries_data()?;
+    info!("loaded {} hourly weather forecasts", forecasts.len());
+    let temperature_forecasts: HourlyForecastSet =
+        convert_hourly_weather_responses(forecasts, create_hourly_temperature_timeseries);
 
-    let seasons = filter_map(column_vec(&common_stats, "season"), 1.0);
+    print_mean_abs_drift(&mean_abs_drift(&forecast_drift(&temperature_forecasts)));
 
-    crate::plot::plot_metric_curves(&seasons, &metrics);
+    info!("Creating plots");
+    HourlyPlot::Chart.plot(&temperature_forecasts)?;
+    HourlyPlot::ChartLevels.plot(&temperature_forecasts)?;
+    HourlyPlot::DriftHeatmap.plot(&temperature_forecasts)?;
 
+    info!("Creating animations");
+    let plot_dir = settings
+        .plot_root
+        .join(data_provider.location().to_string())
+        .join("hourly");
+    for animation_type in [AnimationType::Absolute, AnimationType::Relative] {
+        HourlyPlot::Animation(animation_type, &plot_dir).plot(&temperature_forecasts)?;
+    }
     Ok(())
 }
 
-fn run_weather(settings: &Settings) -> Result<(), AppError> {
+fn run_weather(settings: &Settings, args: &WeatherArgs) -> Result<(), AppError> {
+    match &args.job {
+        Some(WeatherJob::Daemon) => return run_weather_daemon(settings),
+        Some(WeatherJob::Backfill(backfill_args)) => {
+            return run_weather_backfill(settings, backfill_args)
+        }
+        Some(WeatherJob::Ensemble) => return run_weather_ensemble(settings),
+        None => {}
+    }
+    if args.resolution == Resolution::Hourly {
+        return run_weather_hourly(settings);
+    }
+
     let api_key = std::env::var(ENV_API_KEY)?;
 
-    let weather_location =
-        WeatherLocation::new(settings.country_code.clone(), settings.zip.clone());
-    let data_provider = WeatherProvider::new(api_key, weather_location);
+    let weather_location = WeatherLocation::new(
+        settings.country_code.clone(),
+        settings.zip.clone(),
+        parse_timezone(&settings.timezone)?,
+    );
+    // the forecasts are dated in the timezone of the location
+    let now = weather_location.now();
+    let client = HttpClient::new(HttpConfig::default())?;
+    let data_provider = weather_provider(settings, api_key, weather_location, client);
 
     info!("Check updates for forcast data");
-    let today = Utc::now().date();
-    data_provider.update_data(&today)?;
+    {
+        let _lock = DataLock::acquire(&settings.data_root)?;
+        data_provider.update_data(&now)?;
+    }
 
     // forecast curves (retrieved as by date) each containing full weather forecast data
-    let forecasts: Vec<WeatherResponse> = data_provider.load_timeseries_data()?;
+    let mut forecasts: Vec<WeatherResponse> = data_provider.load_timeseries_data()?;
     info!("loaded {} weather forecasts", forecasts.len());
+    let backfilled = forecasts.iter().filter(|fc| fc.backfilled).count();
+    if args.exclude_backfilled {
+        forecasts.retain(|fc| !fc.backfilled);
+        info!("excluded {} backfilled forecasts", backfilled);
+    } else if backfilled > 0 {
+        info!(
+            "{} of the forecasts are backfilled, see --exclude-backfilled",
+            backfilled
+        );
+    }
+
+    // probability of precipitation, verified against the occurrence of precipitation
+    let pop_forecasts: ForecastSet = convert_weather_responses(
+        forecasts.clone(),
+        create_precipitation_probability_timeseries,
+    );
+    let precipitation_forecasts: ForecastSet =
+        convert_weather_responses(forecasts.clone(), create_precipitation_timeseries);
+    let occurrence = precipitation_occurrence(&precipitation_forecasts);
+    print_brier_scores(&brier_scores(&pop_forecasts, &occurrence));
 
     // use max-temperature in the following
-    let temperature_forecasts: Vec<(Date<Utc>, TimeSeries)> =
+    let temperature_forecasts: ForecastSet =
         convert_weather_responses(forecasts, create_temperature_timeseries);
 
-    let reference_ts: Vec<TimeSeriesPoint> = reference_timeseries(&temperature_forecasts);
+    // the stored forecasts for the as-of dates themselves serve as observations
+    let mut climatology = Climatology::from_observations(&temperature_forecasts.diagonal());
+    if let Some(csv_path) = &args.climatology_csv {
+        climatology = Climatology::from_csv(csv_path)?.merge(&climatology);
+    }
+    let baselines: Vec<(Baseline, ForecastSet)> = args
+        .baselines
+        .iter()
+        .map(|baseline| {
+            (
+                *baseline,
+                baseline_forecasts(&temperature_forecasts, *baseline, &climatology),
+            )
+        })
+        .collect();
+
+    let bootstrap = BootstrapConfig {
+        resamples: args.bootstrap_resamples,
+        block_length: args.block_length,
+        confidence: args.confidence,
+        ..BootstrapConfig::default()
+    };
+    let errors = forecast_errors(&temperature_forecasts, &temperature_forecasts.diagonal());
+    for statistic in [
+        ErrorStatistic::Bias,
+        ErrorStatistic::MeanAbsolute,
+        ErrorStatistic::RootMeanSquare,
+    ] {
+        print_error_intervals(
+            statistic,
+            &bootstrap_intervals(&errors, statistic, &bootstrap),
+        );
+    }
+
+    let volatility = volatility_by_valid_date(&revisions(&temperature_forecasts));
+    print_volatility_ranking(&most_volatile_days(&volatility, args.top_volatile));
 
     info!("Creating plots");
-    Plot::Chart(&reference_ts).plot(&temperature_forecasts)?;
-    Plot::ChartLevels(&reference_ts).plot(&temperature_forecasts)?;
-    Plot::ChartLevelTs(&reference_ts).plot(&temperature_forecasts)?;
+    Plot::Chart.plot(&temperature_forecasts)?;
+    Plot::ChartLevels {
+        baselines: &baselines,
+        bootstrap: &bootstrap,
+    }
+    .plot(&temperature_forecasts)?;
+    Plot::ChartLevelTs.plot(&temperature_forecasts)?;
+    Plot::Reliability(&occurrence).plot(&pop_forecasts)?;
+    Plot::Sharpness(&occurrence).plot(&pop_forecasts)?;
+    Plot::Revisions.plot(&temperature_forecasts)?;
 
     info!("Creating animations");
+    let plot_dir = settings
+        .plot_root
+        .join(data_provider.location().to_string());
     // Parallelize the animations as each takes a considerate time
     let forecast_ts_arc = Arc::new(temperature_forecasts);
 
     let abs_fc_ts = forecast_ts_arc.clone();
+    let abs_plot_dir = plot_dir.clone();
     let abs_handle = thread::spawn(move || {
-        Plot::Animation(AnimationType::Absolute)
+        Plot::Animation(AnimationType::Absolute, &abs_plot_dir)
             .plot(abs_fc_ts.as_ref())
             .unwrap();
     });
 
     let rel_handle = thread::spawn(move || {
-        Plot::Animation(AnimationType::create_relative(&reference_ts))
+        Plot::Animation(AnimationType::Relative, &plot_dir)
             .plot(&forecast_ts_arc)
             .unwrap();
     });
@@ -179,6 +1019,168 @@ fn run_weather(settings: &Settings) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Loads the imported COVID-19 figures and compares the countries per metric,
+/// the daily figures as 7-day average.
+fn run_covid(settings: &Settings, args: &CovidArgs) -> Result<(), AppError> {
+    let data_provider = CovidProvider::default().with_data_root(settings.data_root.clone());
+    if let Some(CovidJob::Import(import_args)) = &args.job {
+        let _lock = DataLock::acquire(&settings.data_root)?;
+        return data_provider.import(&import_args.file);
+    }
+
+    let figures = data_provider.load_data()?;
+    info!("loaded the figures of {} countries", figures.len());
+    let figures = filter_countries(&figures, &args.countries);
+    print_country_summary(&figures);
+
+    info!("Creating plots");
+    let range = (
+        args.from.map_or(Bound::Unbounded, Bound::Included),
+        args.to.map_or(Bound::Unbounded, Bound::Included),
+    );
+    for metric in args.metrics.iter() {
+        let mut curves = Vec::new();
+        for country_figures in figures.iter() {
+            let mut ts = metric_series(country_figures, *metric);
+            if metric.is_daily() {
+                ts = seven_day_average(&ts);
+            }
+            if !args.absolute {
+                ts = match per_capita(&ts, country_figures.country.population) {
+                    Some(ts) => ts,
+                    None => {
+                        warn!(
+                            "Skipping {}, the population is not available",
+                            country_figures.country
+                        );
+                        continue;
+                    }
+                };
+            }
+            curves.push((country_figures.country.to_string(), ts.slice(range)));
+        }
+
+        let smoothing = if metric.is_daily() {
+            format!(", {}-day average", ROLLING_DAYS)
+        } else {
+            String::new()
+        };
+        let unit = if args.absolute {
+            "absolute"
+        } else {
+            "per 100'000 inhabitants"
+        };
+        plot_series(
+            &format!("COVID-19 {}{}", metric.name(), smoothing),
+            &format!("{} ({})", metric.name(), unit),
+            &curves,
+        );
+    }
+    Ok(())
+}
+
+/// Loads the configured dataset and plots its value columns, the vintages of datasets with as-of dates
+/// with the plots of the forecasts.
+fn run_dataset(settings: &Settings, args: &DatasetArgs) -> Result<(), AppError> {
+    let config = settings
+        .datasets
+        .iter()
+        .find(|dataset| dataset.name == args.name)
+        .ok_or_else(|| AppError::SetupDataset(format!("{} is not configured", args.name)))?;
+    let client = HttpClient::new(HttpConfig::default())?;
+    let data_provider =
+        DatasetProvider::new(config.clone(), client).with_data_root(settings.data_root.clone());
+
+    let today = chrono::Local::now().naive_local().date();
+    let dataset = data_provider.load(&today)?;
+    info!(
+        "loaded {} records of {}",
+        dataset.records.len(),
+        dataset.name
+    );
+    let series = dataset_series(&dataset);
+    print_dataset_summary(&dataset, &series);
+    let vintages = dataset_vintages(&dataset);
+
+    info!("Creating plots");
+    let plot_dir = settings.plot_root.join("datasets").join(&dataset.name);
+    for plot in args.plots.iter() {
+        if *plot == DatasetPlot::Chart && vintages.is_empty() {
+            plot_series(&dataset.name, "value", &series);
+            continue;
+        }
+        if vintages.is_empty() {
+            warn!(
+                "Skipping the {:?} plot, {} has no as-of dates",
+                plot, dataset.name
+            );
+            continue;
+        }
+        for (column, column_vintages) in vintages.iter() {
+            let labels = PlotLabels {
+                title: format!("Vintages of {}: {}", dataset.name, column),
+                unit: column.clone(),
+            };
+            match plot {
+                DatasetPlot::Chart => Plot::Chart.plot_labelled(column_vintages, &labels)?,
+                DatasetPlot::Levels => Plot::ChartLevels {
+                    baselines: &[],
+                    bootstrap: &BootstrapConfig::default(),
+                }
+                .plot_labelled(column_vintages, &labels)?,
+                DatasetPlot::LevelTs => {
+                    Plot::ChartLevelTs.plot_labelled(column_vintages, &labels)?
+                }
+                DatasetPlot::Revisions => {
+                    Plot::Revisions.plot_labelled(column_vintages, &labels)?
+                }
+                DatasetPlot::Animation => {
+                    let column_dir = plot_dir.join(column);
+                    for animation_type in [AnimationType::Absolute, AnimationType::Relative] {
+                        Plot::Animation(animation_type, &column_dir)
+                            .plot_labelled(column_vintages, &labels)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Creates the data directories of the configured locations, leagues and the COVID-19 figures, and the plot directories of the locations.
+fn run_init(settings: &Settings) -> Result<(), AppError> {
+    let client = HttpClient::new(HttpConfig::default())?;
+    let mut dirs = Vec::new();
+    for (location, _, resolution) in weather_locations(settings)? {
+        dirs.push(settings.plot_root.join(location.to_string()));
+        dirs.push(
+            weather_provider(settings, "NOT_SET".to_string(), location, client.clone())
+                .with_resolution(resolution)
+                .data_path(),
+        );
+    }
+    for league in settings.football_leagues.iter() {
+        dirs.push(
+            football_provider(settings, "NOT_SET".to_string(), league, client.clone()).data_path(),
+        );
+    }
+    dirs.push(
+        CovidProvider::default()
+            .with_data_root(settings.data_root.clone())
+            .data_path(),
+    );
+
+    for dir in dirs.iter() {
+        if dir.exists() {
+            info!("{:?} exists already", dir);
+        } else {
+            std::fs::create_dir_all(dir)?;
+            println!("Created {:?}", dir);
+        }
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), AppError> {
     dotenv().ok(); // This line loads the environment variables from the ".env" file.
 
@@ -188,11 +1190,14 @@ fn main() -> Result<(), AppError> {
     env_logger::Builder::from_env(Env::default().default_filter_or(&log_level)).init();
 
     info!("Starting fun with plots");
-    let settings = Settings::init()?;
+    let settings = Settings::init()?.with_overrides(&args);
 
     match args.data_source {
-        JobArgument::Football => run_football(&settings)?,
-        JobArgument::Weather => run_weather(&settings)?,
+        JobArgument::Init => run_init(&settings)?,
+        JobArgument::Football(football_args) => run_football(&settings, &football_args)?,
+        JobArgument::Weather(weather_args) => run_weather(&settings, &weather_args)?,
+        JobArgument::Dataset(dataset_args) => run_dataset(&settings, &dataset_args)?,
+        JobArgument::Covid(covid_args) => run_covid(&settings, &covid_args)?,
     };
 
     info!("Completed");
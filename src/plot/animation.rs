@@ -1,67 +1,66 @@
-use crate::data::models::TimeSeriesPoint;
-use chrono::{Date, Utc};
+use crate::data::models::{lead_time, ForecastSet, TimeIndex, TimeSeries};
+use crate::plot::LeadUnit;
 use plotters::prelude::*;
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 pub enum AnimationType {
     Absolute,
-    Relative(HashMap<Date<Utc>, f32>), // containing the reference curve
+    Relative, // relative to the reference curve, e.g. the diagonal
 }
 
 impl AnimationType {
-    pub fn create_relative(ref_curve: &[TimeSeriesPoint]) -> AnimationType {
-        let ref_by_date: HashMap<Date<Utc>, f32> =
-            ref_curve.iter().map(|tsp| (tsp.date, tsp.value)).collect();
-        AnimationType::Relative(ref_by_date)
-    }
-
     fn output_file_name(&self, base_dir: &Path) -> PathBuf {
         match self {
             AnimationType::Absolute => base_dir.join("forecast_animation.gif"),
-            AnimationType::Relative(_) => base_dir.join("forecast_relative_animation.gif"),
+            AnimationType::Relative => base_dir.join("forecast_relative_animation.gif"),
         }
     }
 
-    fn y_axis_range(&self) -> std::ops::Range<f32> {
-        match self {
+    /// The fixed range of the temperatures, widened to the values of the curves
+    fn y_axis_range<T: TimeIndex>(&self, curves: &ForecastSet<T>) -> std::ops::Range<f32> {
+        let fixed = match self {
             AnimationType::Absolute => -20.0..45.0,
-            AnimationType::Relative(_) => -20.0..20.0,
-        }
-    }
-
-    fn caption(&self) -> String {
-        match self {
-            AnimationType::Absolute =>  "Weather forcast curve for next 16 days in Celsius: max-temperature for Zurich, CH'".to_string(),
-            AnimationType::Relative(_) =>  "Weather forcast difference (relative to reference) curve for next 16 days in Celsius: max-temperature for Zurich, CH'".to_string(),
-
-        }
+            AnimationType::Relative => -20.0..20.0,
+        };
+        curves
+            .iter()
+            .flat_map(|(_, ts)| ts.iter().map(|tsp| tsp.value))
+            .fold(fixed, |range, v| range.start.min(v)..range.end.max(v))
     }
 
-    fn chart_point(&self, time_series_point: &TimeSeriesPoint) -> Option<f32> {
+    fn caption(&self, title: &str) -> String {
         match self {
-            AnimationType::Absolute => Some(time_series_point.value),
-            AnimationType::Relative(ref_by_date) => ref_by_date
-                .get(&time_series_point.date)
-                .map(|ref_v| time_series_point.value - ref_v),
+            AnimationType::Absolute => title.to_string(),
+            AnimationType::Relative => format!("{} - difference to reference", title),
         }
     }
 }
 
-pub fn plot_time_series_animation(
+/// Renders the animation as gif into the directory, which is created if missing.
+/// The relative animation shows the differences to the reference curve.
+pub fn plot_time_series_animation<T: TimeIndex>(
     animation_type: AnimationType,
-    forecast_timeseries: &[(Date<Utc>, Vec<TimeSeriesPoint>)],
+    forecasts: &ForecastSet<T>,
+    reference: &TimeSeries<T>,
+    base_dir: &Path,
+    title: &str,
+    lead_unit: LeadUnit,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let base_dir = Path::new("plots").join("CH__8001"); // TODO
-    let output_path = animation_type.output_file_name(&base_dir);
+    std::fs::create_dir_all(base_dir)?;
+    let output_path = animation_type.output_file_name(base_dir);
 
     let delay = 1_000;
     let root = BitMapBackend::gif(&output_path, (800, 600), delay)?.into_drawing_area();
 
-    let caption = animation_type.caption();
-    let y_axis_range = animation_type.y_axis_range();
+    let caption = animation_type.caption(title);
 
-    for (as_of_date, ts) in forecast_timeseries {
+    let curves = match animation_type {
+        AnimationType::Absolute => forecasts.clone(),
+        AnimationType::Relative => forecasts.relative_to(reference),
+    };
+    let y_axis_range = animation_type.y_axis_range(&curves);
+
+    for (as_of_date, ts) in curves.iter() {
         root.fill(&WHITE)?;
 
         let mut chart = ChartBuilder::on(&root)
@@ -69,7 +68,7 @@ pub fn plot_time_series_animation(
             .set_label_area_size(LabelAreaPosition::Left, 60)
             .set_label_area_size(LabelAreaPosition::Bottom, 60)
             .build_cartesian_2d::<std::ops::Range<f32>, std::ops::Range<f32>>(
-                0.0..17.0,
+                0.0..lead_unit.axis_max(),
                 y_axis_range.clone(),
             )?;
 
@@ -83,11 +82,9 @@ pub fn plot_time_series_animation(
 
         let points: Vec<(f32, f32)> = ts
             .iter()
-            .enumerate()
-            .flat_map(|(idx, tsp)| {
-                animation_type
-                    .chart_point(tsp)
-                    .map(|v| (idx as f32, v as f32))
+            .map(|tsp| {
+                let lead = lead_unit.of(&lead_time(*as_of_date, tsp.date));
+                (lead, tsp.value)
             })
             .collect();
         // TODO: in absolute case, keep always the previous one and add new one in new color (but only show 2 at the time)
@@ -96,13 +93,10 @@ pub fn plot_time_series_animation(
 
         chart
             .draw_series(series)?
-            .label(&as_of_date.to_string().replace("UTC", ""))
-            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 10, y)], &RED));
+            .label(as_of_date.to_string())
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 10, y)], RED));
 
-        chart
-            .configure_series_labels()
-            .border_style(&BLACK)
-            .draw()?;
+        chart.configure_series_labels().border_style(BLACK).draw()?;
 
         root.present()?;
     }
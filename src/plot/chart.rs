@@ -1,27 +1,31 @@
 use std::collections::HashMap;
 
-use crate::data::models::{TimeSeries, TimeSeriesPoint};
-use plotly::common::{DashType, Line, Marker, Mode, Title};
+use crate::data::models::{TimeIndex, TimeSeries};
+use crate::data::weather::{ConfidenceInterval, ReliabilityBin};
+use crate::plot::LeadUnit;
+use plotly::common::Fill;
+use plotly::common::{DashType, ErrorData, ErrorType, Line, Marker, Mode, Side, Title};
 use plotly::layout::{
     Axis, Layout, Legend, RangeSelector, RangeSlider, SelectorButton, SelectorStep, StepMode,
     TicksDirection,
 };
-use plotly::{Plot, Rgb, Scatter};
+use plotly::{Bar, HeatMap, Plot, Rgb, Scatter, ScatterPolar};
 
-fn unzip(time_series: &[TimeSeriesPoint]) -> (Vec<String>, Vec<f32>) {
+fn unzip<T: TimeIndex>(time_series: &TimeSeries<T>) -> (Vec<String>, Vec<f32>) {
     let mut dates = Vec::new();
     let mut values = Vec::new();
     // skip the first value which is the forecast of today
-    for tsp in time_series[1..].iter() {
+    for tsp in time_series.iter().skip(1) {
         dates.push(tsp.date.to_string());
         values.push(tsp.value);
     }
     (dates, values)
 }
 
-pub fn plot_time_series<T: std::fmt::Display>(
-    reference: &[TimeSeriesPoint],
-    timeseries_collection: &[(T, TimeSeries)],
+pub fn plot_time_series<'a, N: std::fmt::Display, T: TimeIndex + 'a>(
+    title: &str,
+    reference: &TimeSeries<T>,
+    timeseries_collection: impl IntoIterator<Item = (N, &'a TimeSeries<T>)>,
 ) {
     let mut plot = Plot::new();
 
@@ -38,9 +42,7 @@ pub fn plot_time_series<T: std::fmt::Display>(
     }
 
     let layout = Layout::new()
-        .title(Title::new(
-            "Weather forcast curves - 16days, Celsius: max-temperature for Zurich, CH",
-        ))
+        .title(Title::new(title))
         .legend(Legend::new().title(Title::new("Forecast curve as of date")))
         .paper_background_color(Rgb::new(255, 255, 255))
         .plot_background_color(Rgb::new(229, 229, 229))
@@ -95,45 +97,232 @@ pub fn plot_time_series<T: std::fmt::Display>(
     plot.to_inline_html(Some("time_series_with_range_selector_buttons"));
 }
 
-fn unzip_level_curve(curve: &[(chrono::Duration, f32)]) -> (Vec<f32>, Vec<f32>) {
-    let mut days_ahead = Vec::new();
+fn unzip_level_curve(
+    curve: &[(chrono::Duration, f32)],
+    lead_unit: LeadUnit,
+) -> (Vec<f32>, Vec<f32>) {
+    let mut leads = Vec::new();
     let mut values = Vec::new();
-    // skip the first value which is the forecast of today
     for (duration, v) in curve.iter() {
-        days_ahead.push(duration.num_days() as f32);
+        leads.push(lead_unit.of(duration));
         values.push(*v);
     }
-    (days_ahead, values)
+    (leads, values)
 }
 
-pub fn plot_level_curves(curve_by_level: &HashMap<usize, Vec<(chrono::Duration, f32)>>) {
-    let layout = Layout::new().title(Title::new("Percentile Level curves"));
+/// The percentile level curves of the forecast errors with error bars of their confidence intervals
+/// and, on the secondary axis, the skill scores per baseline.
+pub fn plot_level_curves(
+    curve_by_level: &HashMap<usize, Vec<(chrono::Duration, f32)>>,
+    intervals_by_level: &HashMap<usize, Vec<(chrono::Duration, ConfidenceInterval)>>,
+    skill_curves: &[(String, Vec<(chrono::Duration, f32)>)],
+    unit: &str,
+    lead_unit: LeadUnit,
+) {
+    let layout = Layout::new()
+        .title(Title::new("Percentile Level curves"))
+        .x_axis(Axis::new().title(Title::new(lead_unit.label())))
+        .y_axis(Axis::new().title(Title::new(&format!("difference to reference [{}]", unit))))
+        .y_axis2(
+            Axis::new()
+                .title(Title::new("MAE skill score vs. baseline"))
+                .overlaying("y")
+                .side(Side::Right),
+        );
     let mut plot = Plot::new();
 
     for (level, level_curve) in curve_by_level.iter() {
-        let (days_ahead, vs) = unzip_level_curve(level_curve);
-        let trace = Scatter::new(days_ahead, vs)
+        let (leads, vs) = unzip_level_curve(level_curve, lead_unit);
+        let intervals = intervals_by_level.get(level);
+        // the distances of the interval bounds to the level value, zero where no interval is available
+        let (errors_plus, errors_minus): (Vec<f64>, Vec<f64>) = level_curve
+            .iter()
+            .map(|(duration, v)| {
+                intervals
+                    .and_then(|ivs| ivs.iter().find(|(d, _)| d == duration))
+                    .map(|(_, iv)| {
+                        (
+                            (iv.upper - v).max(0.0) as f64,
+                            (v - iv.lower).max(0.0) as f64,
+                        )
+                    })
+                    .unwrap_or((0.0, 0.0))
+            })
+            .unzip();
+        let trace = Scatter::new(leads, vs)
             .mode(Mode::LinesMarkers)
             .name(&format!("Level {}%", level))
             .marker(
                 Marker::new()
                     .color(Rgb::new(3 * (*level as u8), 64, 82))
                     .size(12),
+            )
+            .error_y(
+                ErrorData::new(ErrorType::Data)
+                    .array(errors_plus)
+                    .array_minus(errors_minus),
             );
         plot.add_trace(trace);
     }
 
+    for (baseline, skill_curve) in skill_curves.iter() {
+        let (leads, vs) = unzip_level_curve(skill_curve, lead_unit);
+        let trace = Scatter::new(leads, vs)
+            .mode(Mode::LinesMarkers)
+            .name(&format!("Skill vs. {}", baseline))
+            .line(Line::new().dash(DashType::Dash))
+            .y_axis("y2");
+        plot.add_trace(trace);
+    }
+
     plot.set_layout(layout);
     plot.show();
     println!("{}", plot.to_inline_html(Some("line_and_scatter_styling")));
 }
 
-pub fn plot_metric_curves(base_cuve: &Vec<f32>, metric_curves: &HashMap<&str, Vec<f32>>) {
-    let layout = Layout::new().title(Title::new("Metric curves"));
+/// Plots the members of an ensemble forecast as thin lines along with their mean and the band of
+/// one spread around it, next to other curves such as the deterministic forecast and its error levels.
+pub fn plot_ensemble(
+    title: &str,
+    members: &[&TimeSeries],
+    mean: &TimeSeries,
+    (lower, upper): (&TimeSeries, &TimeSeries),
+    other_curves: &[(String, TimeSeries)],
+) {
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(Axis::new().title(Title::new("valid date")))
+        .y_axis(Axis::new().title(Title::new("Celsius")));
+    let mut plot = Plot::new();
+
+    for member in members.iter() {
+        let (dates, values) = unzip(member);
+        let trace = Scatter::new(dates, values)
+            .mode(Mode::Lines)
+            .line(Line::new().color(Rgb::new(180, 180, 180)).width(0.5))
+            .show_legend(false);
+        plot.add_trace(trace);
+    }
+
+    // the upper bound is filled down to the lower bound added right before
+    let (dates, values) = unzip(lower);
+    let trace = Scatter::new(dates, values)
+        .mode(Mode::Lines)
+        .name("mean - spread")
+        .line(Line::new().color(Rgb::new(3, 64, 82)).width(0.0));
+    plot.add_trace(trace);
+    let (dates, values) = unzip(upper);
+    let trace = Scatter::new(dates, values)
+        .mode(Mode::Lines)
+        .name("mean + spread")
+        .line(Line::new().color(Rgb::new(3, 64, 82)).width(0.0))
+        .fill(Fill::ToNextY);
+    plot.add_trace(trace);
+
+    let (dates, values) = unzip(mean);
+    let trace = Scatter::new(dates, values)
+        .mode(Mode::LinesMarkers)
+        .name("ensemble mean")
+        .line(Line::new().color(Rgb::new(3, 64, 82)).width(3.0));
+    plot.add_trace(trace);
+
+    for (name, ts) in other_curves.iter() {
+        let (dates, values) = unzip(ts);
+        let trace = Scatter::new(dates, values)
+            .mode(Mode::LinesMarkers)
+            .name(name)
+            .line(Line::new().dash(DashType::Dot));
+        plot.add_trace(trace);
+    }
+
+    plot.set_layout(layout);
+    plot.show();
+}
+
+/// Plots the values of a grid as heatmap, e.g. the forecast drift per valid hour (x) and lead time (y).
+pub fn plot_heatmap<
+    X: serde::Serialize + Default + Clone + 'static,
+    Y: serde::Serialize + Default + Clone + 'static,
+>(
+    title: &str,
+    x_label: &str,
+    y_label: &str,
+    xs: Vec<X>,
+    ys: Vec<Y>,
+    values: Vec<Vec<Option<f32>>>,
+) {
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(Axis::new().title(Title::new(x_label)))
+        .y_axis(Axis::new().title(Title::new(y_label)));
+    let mut plot = Plot::new();
+    plot.add_trace(HeatMap::new(xs, ys, values));
+
+    plot.set_layout(layout);
+    plot.show();
+}
+
+pub fn plot_reliability_diagram(bins: &[ReliabilityBin]) {
+    let layout = Layout::new()
+        .title(Title::new(
+            "Reliability diagram - probability of precipitation",
+        ))
+        .x_axis(
+            Axis::new()
+                .title(Title::new("forecast probability"))
+                .range(vec![0.0, 1.0]),
+        )
+        .y_axis(
+            Axis::new()
+                .title(Title::new("observed frequency"))
+                .range(vec![0.0, 1.0]),
+        );
+    let mut plot = Plot::new();
+
+    let perfect = Scatter::new(vec![0.0, 1.0], vec![0.0, 1.0])
+        .mode(Mode::Lines)
+        .name("perfect reliability")
+        .line(Line::new().dash(DashType::Dot));
+    plot.add_trace(perfect);
+
+    let forecast_probabilities: Vec<f32> = bins.iter().map(|b| b.forecast_probability).collect();
+    let observed_frequencies: Vec<f32> = bins.iter().map(|b| b.observed_frequency).collect();
+    let trace = Scatter::new(forecast_probabilities, observed_frequencies)
+        .mode(Mode::LinesMarkers)
+        .name("forecast");
+    plot.add_trace(trace);
+
+    plot.set_layout(layout);
+    plot.show();
+}
+
+pub fn plot_sharpness_histogram(bins: &[ReliabilityBin]) {
+    let layout = Layout::new()
+        .title(Title::new("Sharpness - probability of precipitation"))
+        .x_axis(Axis::new().title(Title::new("forecast probability")))
+        .y_axis(Axis::new().title(Title::new("number of forecasts")));
+    let mut plot = Plot::new();
+
+    let forecast_probabilities: Vec<f32> = bins.iter().map(|b| b.forecast_probability).collect();
+    let counts: Vec<usize> = bins.iter().map(|b| b.count).collect();
+    plot.add_trace(Bar::new(forecast_probabilities, counts).name("forecasts"));
+
+    plot.set_layout(layout);
+    plot.show();
+}
+
+pub fn plot_metric_curves(
+    base_cuve: &[f32],
+    base_name: &str,
+    metric_curves: &HashMap<String, Vec<f32>>,
+) {
+    let layout = Layout::new()
+        .title(Title::new("Metric curves"))
+        .x_axis(Axis::new().title(Title::new(base_name)));
     let mut plot = Plot::new();
 
     for (metric_name, metric_curve) in metric_curves.iter() {
-        let trace = Scatter::new(base_cuve.clone(), metric_curve.clone())
+        let trace = Scatter::new(base_cuve.to_vec(), metric_curve.clone())
             .mode(Mode::LinesMarkers)
             .name(metric_name);
 
@@ -144,3 +333,110 @@ pub fn plot_metric_curves(base_cuve: &Vec<f32>, metric_curves: &HashMap<&str, Ve
     plot.show();
     println!("{}", plot.to_inline_html(Some("line_and_scatter_styling")));
 }
+
+/// Plots the rank trajectories of the players over the seasons, the best rank on top.
+pub fn plot_bump_chart(title: &str, trajectories: &[(String, Vec<(i32, usize)>)]) {
+    let max_rank = trajectories
+        .iter()
+        .flat_map(|(_, ranks)| ranks.iter().map(|(_, rank)| *rank))
+        .max()
+        .unwrap_or(1);
+
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(Axis::new().title(Title::new("season")).dtick(1.0))
+        .y_axis(
+            Axis::new()
+                .title(Title::new("rank"))
+                .range(vec![max_rank as f64 + 0.5, 0.5])
+                .dtick(1.0),
+        );
+    let mut plot = Plot::new();
+
+    for (player_name, ranks) in trajectories.iter() {
+        let (seasons, ranks): (Vec<i32>, Vec<usize>) = ranks.iter().cloned().unzip();
+        let trace = Scatter::new(seasons, ranks)
+            .mode(Mode::LinesMarkers)
+            .name(player_name)
+            .marker(Marker::new().size(12));
+        plot.add_trace(trace);
+    }
+
+    plot.set_layout(layout);
+    plot.show();
+}
+
+/// Plots named time series over their dates, e.g. the same metric for several countries.
+pub fn plot_date_curves(title: &str, y_label: &str, curves: &[(String, TimeSeries)]) {
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(
+            Axis::new()
+                .title(Title::new("date"))
+                .range_slider(RangeSlider::new().visible(true)),
+        )
+        .y_axis(Axis::new().title(Title::new(y_label)));
+    let mut plot = Plot::new();
+
+    for (name, ts) in curves.iter() {
+        let (dates, values): (Vec<String>, Vec<f32>) = ts
+            .iter()
+            .map(|tsp| (tsp.date.to_string(), tsp.value))
+            .unzip();
+        let trace = Scatter::new(dates, values).mode(Mode::Lines).name(name);
+        plot.add_trace(trace);
+    }
+
+    plot.set_layout(layout);
+    plot.show();
+}
+
+/// Plots named curves of (x, y) points, e.g. a metric per season for several teams.
+pub fn plot_xy_curves(
+    title: &str,
+    x_label: &str,
+    y_label: &str,
+    curves: &[(String, Vec<(f32, f32)>)],
+) {
+    let layout = Layout::new()
+        .title(Title::new(title))
+        .x_axis(Axis::new().title(Title::new(x_label)))
+        .y_axis(Axis::new().title(Title::new(y_label)));
+    let mut plot = Plot::new();
+
+    for (name, points) in curves.iter() {
+        let (xs, ys): (Vec<f32>, Vec<f32>) = points.iter().cloned().unzip();
+        let trace = Scatter::new(xs, ys).mode(Mode::LinesMarkers).name(name);
+        plot.add_trace(trace);
+    }
+
+    plot.set_layout(layout);
+    plot.show();
+}
+
+/// The name of an entity with its values per axis, none if missing.
+pub type RadarProfile = (String, Vec<(String, Option<f32>)>);
+
+/// Plots the profiles, i.e. the values per axis (none if missing), of several entities as closed polar traces.
+pub fn plot_radar_chart(title: &str, profiles: &[RadarProfile]) {
+    let layout = Layout::new().title(Title::new(title));
+    let mut plot = Plot::new();
+
+    for (name, profile) in profiles.iter() {
+        // missing values leave a gap in the polygon rather than pulling it to the center
+        let (mut axes, mut values): (Vec<String>, Vec<Option<f32>>) =
+            profile.iter().cloned().unzip();
+        // close the polygon
+        if let (Some(axis), Some(value)) = (axes.first().cloned(), values.first().cloned()) {
+            axes.push(axis);
+            values.push(value);
+        }
+        let trace = ScatterPolar::new(axes, values)
+            .name(name)
+            .fill(Fill::ToSelf);
+        plot.add_trace(trace);
+    }
+
+    plot.set_layout(layout);
+    plot.show();
+}
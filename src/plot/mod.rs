@@ -2,38 +2,284 @@ mod animation;
 mod chart;
 pub use animation::AnimationType;
 
-use crate::data::models::TimeSeriesPoint;
-use crate::data::weather::{percentile_timeseries, percentiles};
+use crate::data::models::{lead_time, ForecastSet, HourlyForecastSet, TimeSeries, TimeSeriesPoint};
+use crate::data::weather::{
+    drift_grid, forecast_drift, percentile_intervals, percentile_timeseries, percentiles,
+    percentiles_relative_to, reliability_diagram, revisions, skill_scores, volatility_by_lead_time,
+    Baseline, BootstrapConfig, EnsembleForecast, Volatility,
+};
 use crate::errors::AppError;
-use chrono::Date;
-use chrono::Utc;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::Path;
 
-pub use crate::plot::chart::plot_metric_curves; // TODO
+pub use crate::plot::chart::{
+    plot_bump_chart, plot_metric_curves, plot_radar_chart, plot_xy_curves, RadarProfile,
+}; // TODO
 
+/// The unit of the lead times on the axes, days for daily and hours for hourly forecasts.
+#[derive(Debug, Clone, Copy)]
+pub enum LeadUnit {
+    Days,
+    Hours,
+}
+
+impl LeadUnit {
+    pub fn of(&self, lead: &chrono::Duration) -> f32 {
+        match self {
+            LeadUnit::Days => lead.num_days() as f32,
+            LeadUnit::Hours => lead.num_hours() as f32,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LeadUnit::Days => "days ahead",
+            LeadUnit::Hours => "hours ahead",
+        }
+    }
+
+    /// The range of the lead times of the forecasts, i.e. 16 days resp. 48 hours
+    fn axis_max(&self) -> f32 {
+        match self {
+            LeadUnit::Days => 17.0,
+            LeadUnit::Hours => 49.0,
+        }
+    }
+}
+
+const DAILY_TITLE: &str =
+    "Weather forcast curves - 16days, Celsius: max-temperature for Zurich, CH";
+const HOURLY_TITLE: &str = "Hourly weather forcast curves - 48 hours, Celsius: temperature";
+
+/// The description of the plotted values in the titles, animation captions and axis labels.
+#[derive(Debug, Clone)]
+pub struct PlotLabels {
+    pub title: String,
+    /// The unit (or else the name) of the values on the value axes
+    pub unit: String,
+}
+
+impl PlotLabels {
+    /// The labels of the daily max-temperature forecasts for Zurich.
+    pub fn daily_weather() -> Self {
+        PlotLabels {
+            title: DAILY_TITLE.to_string(),
+            unit: "Celsius".to_string(),
+        }
+    }
+}
+
+/// The plots of a forecast set, all relative to its reference (diagonal) curve where applicable.
 pub enum Plot<'a> {
-    Chart(&'a [TimeSeriesPoint]),
-    ChartLevels(&'a [TimeSeriesPoint]),  // TODO: unionize
-    ChartLevelTs(&'a [TimeSeriesPoint]), // TODO: unionize
-    Animation(AnimationType),
+    Chart,
+    /// The level curves with their bootstrap confidence intervals,
+    /// alongside the skill scores relative to the baseline forecasts
+    ChartLevels {
+        baselines: &'a [(Baseline, ForecastSet)],
+        bootstrap: &'a BootstrapConfig,
+    },
+    ChartLevelTs,
+    /// The reliability diagram of probability forecasts given the observed occurrence of the event
+    Reliability(&'a TimeSeries),
+    /// The histogram of the forecast probabilities given the observed occurrence of the event
+    Sharpness(&'a TimeSeries),
+    /// The size of the revisions between successive as-of dates per lead time
+    Revisions,
+    /// The animation of the forecast curves, saved into the directory
+    Animation(AnimationType, &'a Path),
+    /// The members of the ensemble forecast with their spread, next to the (latest) forecast
+    /// of the same date with the levels 20% and 80% of its historical errors
+    Ensemble(&'a EnsembleForecast),
 }
 
 impl<'a> Plot<'a> {
-    pub fn plot(
+    /// Plots the daily weather forecasts.
+    pub fn plot(self, forecasts: &ForecastSet) -> Result<(), AppError> {
+        self.plot_labelled(forecasts, &PlotLabels::daily_weather())
+    }
+
+    /// Plots the forecasts with the titles and value axes of the labels.
+    pub fn plot_labelled(
         self,
-        forecast_timeseries: &[(Date<Utc>, Vec<TimeSeriesPoint>)],
+        forecasts: &ForecastSet,
+        labels: &PlotLabels,
     ) -> Result<(), AppError> {
         match self {
-            Plot::Chart(ref_ts) => chart::plot_time_series(ref_ts, forecast_timeseries),
-            Plot::ChartLevelTs(ref_ts) => {
-                let percentile_timeseries = percentile_timeseries(forecast_timeseries);
-                chart::plot_time_series(ref_ts, &percentile_timeseries)
+            Plot::Chart => chart::plot_time_series(&labels.title, &forecasts.diagonal(), forecasts),
+            Plot::ChartLevelTs => {
+                let percentile_timeseries = percentile_timeseries(forecasts);
+                chart::plot_time_series(
+                    &labels.title,
+                    &forecasts.diagonal(),
+                    percentile_timeseries.iter().map(|(level, ts)| (level, ts)),
+                )
+            }
+            Plot::ChartLevels {
+                baselines,
+                bootstrap,
+            } => {
+                let percentiles = percentiles(forecasts);
+                let intervals = percentile_intervals(forecasts, bootstrap);
+                let skill_curves: Vec<(String, Vec<(chrono::Duration, f32)>)> = baselines
+                    .iter()
+                    .map(|(baseline, baseline_forecasts)| {
+                        (
+                            baseline.to_string(),
+                            skill_scores(forecasts, baseline_forecasts),
+                        )
+                    })
+                    .collect();
+                chart::plot_level_curves(
+                    &percentiles,
+                    &intervals,
+                    &skill_curves,
+                    &labels.unit,
+                    LeadUnit::Days,
+                );
+            }
+            Plot::Reliability(occurrence) => {
+                chart::plot_reliability_diagram(&reliability_diagram(forecasts, occurrence))
+            }
+            Plot::Sharpness(occurrence) => {
+                chart::plot_sharpness_histogram(&reliability_diagram(forecasts, occurrence))
+            }
+            Plot::Revisions => {
+                let volatility = volatility_by_lead_time(&revisions(forecasts));
+                let curve = |value: fn(&Volatility) -> f32| -> Vec<(f32, f32)> {
+                    volatility
+                        .iter()
+                        .map(|(days_ahead, v)| (days_ahead.num_days() as f32, value(v)))
+                        .collect()
+                };
+                chart::plot_xy_curves(
+                    "Forecast revisions between successive as-of dates",
+                    "days ahead",
+                    &format!("absolute revision [{}]", labels.unit),
+                    &[
+                        ("mean".to_string(), curve(|v| v.mean_abs_revision)),
+                        ("max".to_string(), curve(|v| v.max_abs_revision)),
+                    ],
+                );
+            }
+            Plot::Animation(animation_type, base_dir) => animation::plot_time_series_animation(
+                animation_type,
+                forecasts,
+                &forecasts.diagonal(),
+                base_dir,
+                &labels.title,
+                LeadUnit::Days,
+            )?,
+            Plot::Ensemble(ensemble) => {
+                let mean = ensemble.mean();
+                let spread = ensemble.spread();
+                let lower = mean.zip_with(&spread, |m, s| Some(m - s));
+                let upper = mean.zip_with(&spread, |m, s| Some(m + s));
+                let members: Vec<&TimeSeries> = ensemble.members.values().collect();
+
+                let deterministic = forecasts
+                    .curve(&ensemble.as_of_date)
+                    .map(|curve| (ensemble.as_of_date, curve))
+                    .or_else(|| forecasts.iter().next_back().map(|(d, c)| (*d, c)));
+                let mut other_curves = Vec::new();
+                if let Some((as_of_date, curve)) = deterministic {
+                    other_curves.push((format!("forecast as of {}", as_of_date), curve.clone()));
+                    for level in [20, 80] {
+                        other_curves.push((
+                            format!("forecast error level {}%", level),
+                            error_level_curve(forecasts, as_of_date, curve, level),
+                        ));
+                    }
+                }
+
+                chart::plot_ensemble(
+                    &format!(
+                        "Ensemble forecast as of {}, Celsius: max-temperature",
+                        ensemble.as_of_date
+                    ),
+                    &members,
+                    &mean,
+                    (&lower, &upper),
+                    &other_curves,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The forecast curve shifted by the percentile level of the historical forecast errors per lead time.
+fn error_level_curve(
+    forecasts: &ForecastSet,
+    as_of_date: NaiveDate,
+    curve: &TimeSeries,
+    level: usize,
+) -> TimeSeries {
+    let level_curve = percentiles(forecasts).remove(&level).unwrap_or_default();
+    curve
+        .iter()
+        .flat_map(|tsp| {
+            let lead = lead_time(as_of_date, tsp.date);
+            let (_, error) = level_curve.iter().find(|(d, _)| *d == lead)?;
+            Some(TimeSeriesPoint {
+                date: tsp.date,
+                value: tsp.value + error,
+            })
+        })
+        .collect()
+}
+
+/// Charts the named series over their dates, e.g. the value columns of a dataset.
+pub fn plot_series(title: &str, y_label: &str, series: &[(String, TimeSeries)]) {
+    chart::plot_date_curves(title, y_label, series);
+}
+
+/// The plots of hourly forecasts, relative to the latest forecast of each hour where applicable (see `forecast_drift`).
+pub enum HourlyPlot<'a> {
+    Chart,
+    /// The percentile levels of the drift per lead time (in hours)
+    ChartLevels,
+    /// The drift per valid hour and lead time
+    DriftHeatmap,
+    Animation(AnimationType, &'a Path),
+}
+
+impl<'a> HourlyPlot<'a> {
+    pub fn plot(self, forecasts: &HourlyForecastSet) -> Result<(), AppError> {
+        match self {
+            HourlyPlot::Chart => {
+                chart::plot_time_series(HOURLY_TITLE, &forecasts.latest(), forecasts)
+            }
+            HourlyPlot::ChartLevels => {
+                let percentiles = percentiles_relative_to(forecasts, &forecasts.latest());
+                chart::plot_level_curves(
+                    &percentiles,
+                    &HashMap::new(),
+                    &[],
+                    "Celsius",
+                    LeadUnit::Hours,
+                );
             }
-            Plot::ChartLevels(ref_ts) => {
-                let percentiles = percentiles(ref_ts, forecast_timeseries);
-                chart::plot_level_curves(&percentiles);
+            HourlyPlot::DriftHeatmap => {
+                let grid = drift_grid(&forecast_drift(forecasts));
+                chart::plot_heatmap(
+                    "Drift of the hourly forecasts relative to the latest forecast [Celsius]",
+                    "valid hour",
+                    "hours ahead",
+                    grid.valid_times.iter().map(|t| t.to_string()).collect(),
+                    grid.lead_hours,
+                    grid.values,
+                );
             }
-            Plot::Animation(animation_type) => {
-                animation::plot_time_series_animation(animation_type, forecast_timeseries)?
+            HourlyPlot::Animation(animation_type, base_dir) => {
+                animation::plot_time_series_animation(
+                    animation_type,
+                    forecasts,
+                    &forecasts.latest(),
+                    base_dir,
+                    HOURLY_TITLE,
+                    LeadUnit::Hours,
+                )?
             }
         }
         Ok(())
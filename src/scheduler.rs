@@ -0,0 +1,171 @@
+use crate::data::{DataLock, WeatherProvider};
+use crate::errors::AppError;
+use chrono::{DateTime, Duration, Utc};
+use cron::Schedule;
+use log::{error, info, warn};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// The maximal time to sleep at once, such that a shutdown request is handled promptly.
+const SLEEP_INTERVAL_SECONDS: i64 = 1;
+/// The delay of a run after which it is logged as late, e.g. after the machine was suspended.
+const LATE_RUN_MINUTES: i64 = 5;
+/// The attempts to acquire the data lock for a run, e.g. while an interactive run fetches the same data.
+const LOCK_ATTEMPTS: u32 = 30;
+const LOCK_RETRY_SECONDS: u64 = 2;
+
+pub fn parse_schedule(expression: &str) -> Result<Schedule, AppError> {
+    Schedule::from_str(expression)
+        .map_err(|e| AppError::SetupSchedule(format!("'{}' ({})", expression, e)))
+}
+
+/// Fetches the forecasts of a location according to the (cron) schedule, evaluated in the timezone of the location.
+pub struct ScheduledFetch {
+    pub provider: WeatherProvider,
+    pub schedule: Schedule,
+}
+
+impl ScheduledFetch {
+    pub fn new(provider: WeatherProvider, schedule: Schedule) -> Self {
+        Self { provider, schedule }
+    }
+
+    fn next_run(&self, after: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let timezone = self.provider.location().timezone;
+        self.schedule
+            .after(&after.with_timezone(&timezone))
+            .next()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Fetches the forecast while holding the data lock, waiting for other runs to release it.
+    fn run(&self, data_root: &Path) {
+        let location = self.provider.location();
+        let _lock = match acquire_lock(data_root) {
+            Ok(lock) => lock,
+            Err(e) => {
+                error!("Skipping the fetch of the forecast for {}: {}", location, e);
+                return;
+            }
+        };
+        info!("Fetching the forecast for {}", location);
+        if let Err(e) = self.provider.update_data(&location.now()) {
+            error!("Failed to fetch the forecast for {}: {}", location, e);
+        }
+    }
+
+    /// Logs the days without stored forecasts and runs the last scheduled run of the past day if it was missed.
+    fn catch_up(&self, data_root: &Path) {
+        let location = self.provider.location();
+        let now = location.now();
+
+        if let Some(latest) = self.provider.latest_as_of_date() {
+            let missing_days = (now.naive_local().date() - latest).num_days() - 1;
+            if missing_days > 0 {
+                warn!(
+                    "{} day(s) without forecasts for {} since {}",
+                    missing_days, location, latest
+                );
+            }
+        }
+
+        let last_scheduled = self
+            .schedule
+            .after(&(now - Duration::days(1)))
+            .take_while(|t| *t <= now)
+            .last();
+        if let Some(scheduled) = last_scheduled {
+            // the run is missed if the forecast of its as-of date (resp. hour) is not stored, unless the date has passed already
+            let as_of_date = scheduled.naive_local().date();
+            if as_of_date == now.naive_local().date() && !self.provider.has_run(&scheduled) {
+                warn!(
+                    "Missed the scheduled run at {} for {}, catching up",
+                    scheduled, location
+                );
+                self.run(data_root);
+            }
+        }
+    }
+}
+
+fn acquire_lock(data_root: &Path) -> Result<DataLock, AppError> {
+    let mut attempt = 1;
+    loop {
+        match DataLock::acquire(data_root) {
+            Err(AppError::Locked(path)) if attempt < LOCK_ATTEMPTS => {
+                info!("{:?} is locked by another run, retrying", path);
+                attempt += 1;
+                thread::sleep(std::time::Duration::from_secs(LOCK_RETRY_SECONDS));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Sleeps until the time, returns false if the shutdown was requested in the meantime.
+fn sleep_until(time: &DateTime<Utc>, shutdown: &AtomicBool) -> bool {
+    loop {
+        if shutdown.load(Ordering::SeqCst) {
+            return false;
+        }
+        let remaining = *time - Utc::now();
+        if remaining <= Duration::zero() {
+            return true;
+        }
+        let interval = remaining.min(Duration::seconds(SLEEP_INTERVAL_SECONDS));
+        thread::sleep(interval.to_std().unwrap_or_default());
+    }
+}
+
+/// Runs the scheduled fetches until SIGINT or SIGTERM is received.
+/// The data directory is locked during each fetch only, such that other runs can use the stored data in between.
+pub fn run_daemon(data_root: &Path, fetches: Vec<ScheduledFetch>) -> Result<(), AppError> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let shutdown_handle = shutdown.clone();
+    ctrlc::set_handler(move || {
+        info!("Shutdown requested");
+        shutdown_handle.store(true, Ordering::SeqCst);
+    })
+    .map_err(|e| AppError::Dynamic(Box::new(e)))?;
+
+    for fetch in fetches.iter() {
+        fetch.catch_up(data_root);
+    }
+
+    loop {
+        let now = Utc::now();
+        let next_run = match fetches.iter().flat_map(|f| f.next_run(&now)).min() {
+            Some(next_run) => next_run,
+            None => {
+                warn!("No further scheduled runs");
+                break;
+            }
+        };
+        info!("Next run at {}", next_run);
+
+        if !sleep_until(&next_run, &shutdown) {
+            break;
+        }
+
+        let delay = Utc::now() - next_run;
+        if delay > Duration::minutes(LATE_RUN_MINUTES) {
+            warn!(
+                "The run scheduled at {} is late by {} minutes",
+                next_run,
+                delay.num_minutes()
+            );
+        }
+        // all fetches due at the time of the run
+        for fetch in fetches.iter() {
+            if fetch.next_run(&now) == Some(next_run) {
+                fetch.run(data_root);
+            }
+        }
+    }
+
+    info!("Daemon stopped");
+    Ok(())
+}